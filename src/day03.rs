@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Copy)]
 struct PartNumber<'a>(pub usize, pub &'a str);
@@ -13,16 +13,94 @@ impl<'a> PartNumber<'a> {
     }
 }
 
-fn part02(input: &str) -> u32 {
-    let column_count = input.lines().next().unwrap().len();
-    let row_count = input.lines().count();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PartCount {
+    Exact(usize),
+    #[allow(dead_code)]
+    Min(usize),
+    #[allow(dead_code)]
+    Max(usize),
+}
 
-    let input_joined = input
-        .chars()
-        .filter(|c| !c.is_ascii_whitespace())
-        .collect::<String>();
+impl PartCount {
+    fn matches(&self, count: usize) -> bool {
+        match *self {
+            PartCount::Exact(n) => count == n,
+            PartCount::Min(n) => count >= n,
+            PartCount::Max(n) => count <= n,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregation {
+    Product,
+    #[allow(dead_code)]
+    Sum,
+    #[allow(dead_code)]
+    Max,
+}
+
+impl Aggregation {
+    fn apply(&self, numbers: &[u32]) -> u32 {
+        let numbers = numbers.iter().copied();
+        match self {
+            Aggregation::Product => numbers.reduce(|acc, n| acc * n),
+            Aggregation::Sum => numbers.reduce(|acc, n| acc + n),
+            Aggregation::Max => numbers.max(),
+        }
+        .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GearRule {
+    symbol: char,
+    count: PartCount,
+    aggregation: Aggregation,
+}
+
+impl GearRule {
+    const STANDARD: GearRule = GearRule {
+        symbol: '*',
+        count: PartCount::Exact(2),
+        aggregation: Aggregation::Product,
+    };
+
+    fn is_gear(&self, symbol: &Symbol) -> bool {
+        symbol.symbol == self.symbol && self.count.matches(symbol.parts.len())
+    }
 
-    let parts = input
+    fn ratio(&self, symbol: &Symbol) -> Option<u32> {
+        if self.is_gear(symbol) {
+            Some(self.aggregation.apply(&symbol.parts))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Symbol {
+    symbol: char,
+    row: usize,
+    column: usize,
+    parts: Vec<u32>,
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' at ({}, {}):", self.symbol, self.row, self.column)?;
+        for (i, part) in self.parts.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{separator}{part}")?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_parts<'a>(input: &'a str, input_joined: &str, column_count: usize) -> Vec<PartNumber<'a>> {
+    input
         .lines()
         .enumerate()
         .flat_map(|(line_number, line)| {
@@ -41,15 +119,27 @@ fn part02(input: &str) -> u32 {
 
             numbers
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+fn symbol_report(input: &str) -> Vec<Symbol> {
+    let column_count = input.lines().next().unwrap().len();
+    let row_count = input.lines().count();
+
+    let input_joined = input
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<String>();
+
+    let parts = parse_parts(input, &input_joined, column_count);
 
     input_joined
         .char_indices()
-        .filter_map(|(idx, c)| if c == '*' { Some(idx) } else { None })
-        .map(|idx| {
+        .filter(|&(_, c)| c.is_ascii_punctuation() && c != '.')
+        .map(|(idx, symbol)| {
             let (row, column) = ((idx / column_count) as i32, (idx % column_count) as i32);
 
-            let mut adjacent_parts = HashMap::new();
+            let mut adjacent_parts = BTreeMap::new();
 
             for row_add in -1..=1 {
                 for column_add in -1..=1 {
@@ -67,24 +157,32 @@ fn part02(input: &str) -> u32 {
                     let part_index = r as usize * (column_count) + c as usize;
 
                     if let Some(part) = parts.iter().find(|&part| part.intersects(part_index)) {
-                        adjacent_parts.insert(part.0, part);
+                        adjacent_parts.insert(part.0, part.number());
                     }
                 }
             }
 
-            if adjacent_parts.len() == 2 {
-                adjacent_parts
-                    .into_values()
-                    .map(|part| part.number())
-                    .reduce(|acc, a| acc * a)
-                    .unwrap()
-            } else {
-                0
+            Symbol {
+                symbol,
+                row: row as usize,
+                column: column as usize,
+                parts: adjacent_parts.into_values().collect(),
             }
         })
+        .collect()
+}
+
+fn sum_gear_ratios(input: &str, rule: &GearRule) -> u32 {
+    symbol_report(input)
+        .iter()
+        .filter_map(|symbol| rule.ratio(symbol))
         .sum()
 }
 
+fn part02(input: &str) -> u32 {
+    sum_gear_ratios(input, &GearRule::STANDARD)
+}
+
 fn part01(input: &str) -> u32 {
     let numbers = input
         .lines()
@@ -176,4 +274,72 @@ mod tests {
 
         assert_eq!(super::part02(input), 467835);
     }
+
+    #[test]
+    fn symbol_report() {
+        let input = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+        let report = super::symbol_report(input)
+            .iter()
+            .map(|symbol| symbol.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            report,
+            vec![
+                "'*' at (1, 3): 467, 35",
+                "'#' at (3, 6): 633",
+                "'*' at (4, 3): 617",
+                "'+' at (5, 5): 592",
+                "'$' at (8, 3): 664",
+                "'*' at (8, 5): 755, 598",
+            ]
+        );
+    }
+
+    #[test]
+    fn gear_rules() {
+        use super::{Aggregation, GearRule, PartCount};
+
+        let input = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+        let rule = GearRule {
+            symbol: '*',
+            count: PartCount::Min(1),
+            aggregation: Aggregation::Sum,
+        };
+        assert_eq!(super::sum_gear_ratios(input, &rule), 2472);
+
+        let rule = GearRule {
+            symbol: '*',
+            count: PartCount::Max(1),
+            aggregation: Aggregation::Max,
+        };
+        assert_eq!(super::sum_gear_ratios(input, &rule), 617);
+
+        let rule = GearRule {
+            symbol: '#',
+            count: PartCount::Exact(1),
+            aggregation: Aggregation::Product,
+        };
+        assert_eq!(super::sum_gear_ratios(input, &rule), 633);
+    }
 }
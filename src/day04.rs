@@ -1,86 +1,198 @@
-use std::collections::HashMap;
-
-fn count_matching_cards(content: &str) -> u32 {
-    let (winning_cards, my_cards) = content.trim().split_once('|').unwrap();
-    let winning_cards = winning_cards
-        .trim()
-        .split(' ')
-        .filter(|n| !n.is_empty())
-        .map(|n| n.parse::<u32>().unwrap())
-        .collect::<Vec<_>>();
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct NumberSet(u128);
 
-    let my_cards = my_cards
-        .trim()
-        .split(' ')
-        .filter(|n| !n.is_empty())
-        .map(|n| n.parse::<u32>().unwrap())
-        .collect::<Vec<_>>();
+impl NumberSet {
+    fn parse(numbers: &str) -> NumberSet {
+        numbers
+            .split(' ')
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<u32>().unwrap())
+            .fold(NumberSet::default(), |mut set, n| {
+                set.insert(n);
+                set
+            })
+    }
 
-    my_cards
-        .iter()
-        .filter(|n| winning_cards.contains(n))
-        .count() as u32
+    fn insert(&mut self, n: u32) {
+        assert!(n < 100, "Card numbers must be between 0 and 99, got {n}");
+        self.0 |= 1 << n;
+    }
+
+    fn common_count(&self, other: &NumberSet) -> u32 {
+        (self.0 & other.0).count_ones()
+    }
 }
 
-fn part02(input: &str) -> u32 {
-    let mut cards = input
-        .lines()
-        .map(|line| {
-            let (card, content) = line.split_once(':').unwrap();
-            let card = card
-                .matches(|c: char| c.is_ascii_digit())
-                .collect::<String>()
-                .parse::<u32>()
-                .unwrap();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Card {
+    number: u32,
+    winning: NumberSet,
+    mine: NumberSet,
+}
+
+impl Card {
+    fn parse(line: &str) -> Card {
+        let (card, content) = line.split_once(':').unwrap();
+        let number = card
+            .matches(|c: char| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<u32>()
+            .unwrap();
 
-            let count = count_matching_cards(content);
+        let (winning, mine) = content.trim().split_once('|').unwrap();
 
-            (card, count)
+        Card {
+            number,
+            winning: NumberSet::parse(winning),
+            mine: NumberSet::parse(mine),
+        }
+    }
+
+    fn matches(&self) -> u32 {
+        self.winning.common_count(&self.mine)
+    }
+}
+
+fn parse_cards(input: &str) -> Vec<Card> {
+    input.lines().map(Card::parse).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scoring {
+    Doubling,
+    #[allow(dead_code)]
+    Linear,
+    #[allow(dead_code)]
+    Fibonacci,
+}
+
+impl Scoring {
+    fn score(&self, matches: u32) -> u32 {
+        match self {
+            Scoring::Doubling if matches == 0 => 0,
+            Scoring::Doubling => 2u32.pow(matches - 1),
+            Scoring::Linear => matches,
+            Scoring::Fibonacci => (0..matches).fold((0, 1), |(a, b), _| (b, a + b)).0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CopyRule {
+    Following,
+    #[allow(dead_code)]
+    Limited(u32),
+    #[allow(dead_code)]
+    Wrapping,
+}
+
+impl CopyRule {
+    fn targets(&self, index: usize, matches: u32, len: usize) -> impl Iterator<Item = usize> {
+        let (count, wrap) = match *self {
+            CopyRule::Following => ((len - 1 - index).min(matches as usize), false),
+            CopyRule::Limited(n) => ((len - 1 - index).min(matches.min(n) as usize), false),
+            CopyRule::Wrapping => ((len - 1).min(matches as usize), true),
+        };
+
+        (index + 1..=index + count).map(move |next| if wrap { next % len } else { next })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CardError {
+    MissingCard(u32),
+    DuplicatedCard(u32),
+}
+
+impl std::fmt::Display for CardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardError::MissingCard(card) => write!(f, "Card {card} is missing"),
+            CardError::DuplicatedCard(card) => write!(f, "Card {card} appears more than once"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CardBreakdown {
+    card: u32,
+    matches: u32,
+    copies: u32,
+    contributors: Vec<(u32, u32)>,
+}
+
+impl std::fmt::Display for CardBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Card {}: {} matches, {} copies (1 original",
+            self.card, self.matches, self.copies
+        )?;
+        for (card, copies) in &self.contributors {
+            write!(f, ", {copies} from card {card}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+fn copies_breakdown(cards: &[Card], rule: CopyRule) -> Result<Vec<CardBreakdown>, CardError> {
+    let mut cards = cards.to_vec();
+    cards.sort_by_key(|card| card.number);
+
+    for pair in cards.windows(2) {
+        let (previous, current) = (pair[0].number, pair[1].number);
+        if previous == current {
+            return Err(CardError::DuplicatedCard(current));
+        } else if previous + 1 != current {
+            return Err(CardError::MissingCard(previous + 1));
+        }
+    }
+
+    let mut breakdown = cards
+        .iter()
+        .map(|card| CardBreakdown {
+            card: card.number,
+            matches: card.matches(),
+            copies: 1,
+            contributors: vec![],
         })
         .collect::<Vec<_>>();
 
-    cards.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let len = breakdown.len();
+    for index in 0..len {
+        let (card, matches, copies) = {
+            let current = &breakdown[index];
+            (current.card, current.matches, current.copies)
+        };
 
-    let card_copies = cards
-        .iter()
-        .map(|(c, _)| (*c, 1u32))
-        .collect::<HashMap<_, _>>();
+        // Copies wrapped around to an earlier card are counted, but that card
+        // was already processed, so they don't win any further copies.
+        for next in rule.targets(index, matches, len) {
+            breakdown[next].copies += copies;
+            breakdown[next].contributors.push((card, copies));
+        }
+    }
 
-    let max = *cards.iter().map(|(card, _)| card).max().unwrap();
+    Ok(breakdown)
+}
 
-    cards
-        .into_iter()
-        .fold(
-            (card_copies, 0),
-            |(mut card_copies, card_count), (card, winning)| {
-                let copies = card_copies.remove(&card).unwrap();
-                let end_card = (card + winning).min(max);
+fn total_score(cards: &[Card], scoring: Scoring) -> u32 {
+    cards.iter().map(|card| scoring.score(card.matches())).sum()
+}
 
-                for next_card in (card + 1)..=end_card {
-                    *card_copies.get_mut(&next_card).unwrap() += copies;
-                }
+fn total_copies(cards: &[Card], rule: CopyRule) -> Result<u32, CardError> {
+    Ok(copies_breakdown(cards, rule)?
+        .iter()
+        .map(|card| card.copies)
+        .sum())
+}
 
-                (card_copies, card_count + copies)
-            },
-        )
-        .1
+fn part02(input: &str) -> u32 {
+    total_copies(&parse_cards(input), CopyRule::Following).unwrap()
 }
 
 fn part01(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|line| {
-            let (_, content) = line.split_once(':').unwrap();
-
-            let count = count_matching_cards(content);
-
-            if count == 0 {
-                count
-            } else {
-                2u32.pow(count - 1)
-            }
-        })
-        .sum()
+    total_score(&parse_cards(input), Scoring::Doubling)
 }
 
 fn main() {
@@ -115,4 +227,82 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
         assert_eq!(super::part02(input), 30);
     }
+
+    #[test]
+    fn copies_breakdown() {
+        let input = "Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36";
+
+        let breakdown =
+            super::copies_breakdown(&super::parse_cards(input), super::CopyRule::Following)
+                .unwrap();
+
+        assert_eq!(
+            breakdown.iter().map(|c| c.copies).collect::<Vec<_>>(),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+        assert_eq!(
+            breakdown[4].to_string(),
+            "Card 5: 0 matches, 14 copies (1 original, 1 from card 1, 4 from card 3, 8 from card 4)"
+        );
+    }
+
+    #[test]
+    fn copies_breakdown_errors() {
+        use super::CardError;
+
+        let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1";
+        assert_eq!(
+            super::copies_breakdown(&super::parse_cards(input), super::CopyRule::Following),
+            Err(CardError::MissingCard(2))
+        );
+
+        let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 1:  1 21 53 59 44 | 69 82 63 72 16 21 14  1";
+        assert_eq!(
+            super::copies_breakdown(&super::parse_cards(input), super::CopyRule::Following),
+            Err(CardError::DuplicatedCard(1))
+        );
+    }
+
+    #[test]
+    fn rule_variants() {
+        use super::{CopyRule, Scoring};
+
+        let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+        let cards = super::parse_cards(input);
+
+        assert_eq!(
+            cards.iter().map(|c| c.matches()).collect::<Vec<_>>(),
+            vec![4, 2, 2, 1, 0, 0]
+        );
+
+        assert_eq!(super::total_score(&cards, Scoring::Linear), 9);
+        assert_eq!(super::total_score(&cards, Scoring::Fibonacci), 6);
+
+        assert_eq!(super::total_copies(&cards, CopyRule::Limited(1)), Ok(16));
+        assert_eq!(super::total_copies(&cards, CopyRule::Limited(4)), Ok(30));
+
+        let cards = super::parse_cards(
+            "Card 1: 1 | 2
+Card 2: 1 2 | 1 2
+Card 3: 1 | 1",
+        );
+        let breakdown = super::copies_breakdown(&cards, CopyRule::Wrapping).unwrap();
+        assert_eq!(
+            breakdown.iter().map(|c| c.copies).collect::<Vec<_>>(),
+            vec![4, 1, 2]
+        );
+    }
 }
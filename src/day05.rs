@@ -1,36 +1,6 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Range {
-    start: u64,
-    lenght: u64,
-}
-
-impl Range {
-    fn iter(&self) -> impl Iterator<Item = u64> {
-        self.start..(self.start + self.lenght).into()
-    }
+use std::collections::{HashMap, HashSet};
 
-    fn end(&self) -> u64 {
-        self.start + self.lenght - 1
-    }
-}
-
-impl From<&[u64]> for Range {
-    fn from(value: &[u64]) -> Self {
-        Range {
-            start: value[0],
-            lenght: value[1],
-        }
-    }
-}
-
-impl From<u64> for Range {
-    fn from(value: u64) -> Self {
-        Range {
-            start: value,
-            lenght: 1,
-        }
-    }
-}
+use advent_of_code::interval::IntervalSet;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct MapRange {
@@ -41,7 +11,12 @@ struct MapRange {
 
 impl MapRange {
     fn source_end(&self) -> u64 {
-        self.source + self.length - 1
+        self.source + self.length.saturating_sub(1)
+    }
+
+    fn overflows(&self) -> bool {
+        let last = self.length.saturating_sub(1);
+        self.source.checked_add(last).is_none() || self.destination.checked_add(last).is_none()
     }
 
     fn map_single(&self, target: u64) -> Option<u64> {
@@ -53,48 +28,27 @@ impl MapRange {
         }
     }
 
-    fn map_range(&self, target: Range) -> (Option<Range>, Vec<Range>) {
-        if let Some((inside, outside)) = self.split(target) {
-            let offset = inside.start - self.source;
-            let mapped = Range {
-                start: self.destination + offset,
-                ..inside
-            };
-            (Some(mapped), outside)
-        } else {
-            (None, vec![target])
+    #[cfg(test)]
+    fn inverse(&self) -> MapRange {
+        MapRange {
+            destination: self.source,
+            source: self.destination,
+            length: self.length,
         }
     }
 
-    fn split(&self, target: Range) -> Option<(Range, Vec<Range>)> {
-        if target.end() < self.source || target.start > self.source_end() {
-            return None;
-        }
-
-        let mut outside = vec![];
-        let left_split = target.start.max(self.source);
-        let right_split = target.end().min(self.source_end());
-
-        let inside = Range {
-            start: left_split,
-            lenght: right_split - left_split + 1,
-        };
-
-        if left_split > target.start {
-            outside.push(Range {
-                start: target.start,
-                lenght: left_split - target.start,
-            })
-        };
+    fn source_range(&self) -> IntervalSet {
+        (self.source..self.source.saturating_add(self.length)).into()
+    }
 
-        if right_split < target.end() {
-            outside.push(Range {
-                start: right_split + 1,
-                lenght: target.end() - right_split,
-            })
-        }
+    fn map_range(&self, targets: &IntervalSet) -> (IntervalSet, IntervalSet) {
+        let source = self.source_range();
+        let inside = targets.intersection(&source);
 
-        Some((inside, outside))
+        (
+            inside.shift(self.source, self.destination),
+            targets.difference(&source),
+        )
     }
 }
 
@@ -112,194 +66,495 @@ impl From<&str> for MapRange {
     }
 }
 
-#[derive(Debug, Clone, Default)]
-struct Alamanac {
-    seeds: Vec<Range>,
-    seed_to_soil: Vec<MapRange>,
-    soil_to_fertilizer: Vec<MapRange>,
-    fertilizer_to_water: Vec<MapRange>,
-    water_to_light: Vec<MapRange>,
-    light_to_temperature: Vec<MapRange>,
-    temperature_to_humidity: Vec<MapRange>,
-    humidity_to_location: Vec<MapRange>,
-}
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct PiecewiseMap(Vec<MapRange>);
 
-impl Alamanac {
-    fn new(seeds: Vec<Range>) -> Self {
-        Self {
-            seeds,
-            ..Default::default()
-        }
+impl PiecewiseMap {
+    fn new(mut ranges: Vec<MapRange>) -> Self {
+        ranges.retain(|range| range.length > 0);
+        ranges.sort_by_key(|range| range.source);
+        Self(ranges)
     }
 
-    fn find_lowest_location(&self) -> u64 {
-        self.seeds
-            .iter()
-            .flat_map(|seed| seed.iter())
-            .into_iter()
-            .map(|seed| self.get_seed_location(seed))
-            .min()
-            .unwrap()
+    fn find(&self, target: u64) -> Option<&MapRange> {
+        let index = self.0.partition_point(|range| range.source_end() < target);
+        self.0.get(index).filter(|range| range.source <= target)
     }
 
-    fn find_lowest_ranged_location(&self) -> u64 {
-        self.seeds
+    // Splits the inclusive interval [start, end] into (start, end, mapped start) segments,
+    // where gaps between ranges map to themselves.
+    fn segments(&self, start: u64, end: u64) -> Vec<(u64, u64, u64)> {
+        let mut segments = vec![];
+        let mut current = start;
+
+        let first = self.0.partition_point(|range| range.source_end() < start);
+        for range in self.0[first..]
             .iter()
-            .map(|&seed| self.get_seed_range_location(seed))
-            .flat_map(|ranges| ranges.into_iter().map(|range| range.start))
-            .min()
-            .unwrap()
+            .take_while(|range| range.source <= end)
+        {
+            if current < range.source {
+                segments.push((current, range.source - 1, current));
+                current = range.source;
+            }
+
+            let segment_end = end.min(range.source_end());
+            segments.push((current, segment_end, range.map_single(current).unwrap()));
+
+            if segment_end == end {
+                return segments;
+            }
+            current = segment_end + 1;
+        }
+
+        segments.push((current, end, current));
+        segments
     }
 
-    fn find_range_mapping(&self, targets: &[Range], ranges: &[MapRange]) -> Vec<Range> {
-        let (mapped_targets, unmapped) = ranges.iter().fold(
-            (vec![], targets.to_vec()),
-            |(mut mapped_targets, unmapped_targets), &map_range| {
-                let (mapped, unmapped): (Vec<_>, Vec<_>) = unmapped_targets
-                    .iter()
-                    .map(|&target| map_range.map_range(target))
-                    .unzip();
+    fn compose(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut ranges: Vec<MapRange> = vec![];
 
-                let mut mapped = mapped.into_iter().filter_map(|r| r).collect::<Vec<_>>();
-                let unmapped_targets = unmapped.into_iter().flatten().collect::<Vec<_>>();
+        for (start, end, mapped) in self.segments(0, u64::MAX) {
+            for (next_start, next_end, next_mapped) in next.segments(mapped, mapped + (end - start))
+            {
+                let source = start + (next_start - mapped);
+                if source == next_mapped {
+                    continue;
+                }
 
-                mapped_targets.append(&mut mapped);
-                (mapped_targets, unmapped_targets)
-            },
-        );
+                let length = next_end - next_start + 1;
+                match ranges.last_mut() {
+                    Some(last)
+                        if last.source + last.length == source
+                            && last.destination + last.length == next_mapped =>
+                    {
+                        last.length += length
+                    }
+                    _ => ranges.push(MapRange {
+                        destination: next_mapped,
+                        source,
+                        length,
+                    }),
+                }
+            }
+        }
 
-        mapped_targets
-            .into_iter()
-            .chain(unmapped.into_iter())
-            .collect()
+        PiecewiseMap(ranges)
     }
 
-    fn find_mapping(&self, target: u64, ranges: &[MapRange]) -> u64 {
-        ranges
-            .iter()
-            .find_map(|range| range.map_single(target))
+    fn map_single(&self, target: u64) -> u64 {
+        self.find(target)
+            .and_then(|range| range.map_single(target))
             .unwrap_or(target)
     }
 
-    fn get_seed_range_location(&self, seed: Range) -> Vec<Range> {
-        let soil = self.find_range_mapping(&vec![seed], &self.seed_to_soil);
-        let fertilizer = self.find_range_mapping(&soil, &self.soil_to_fertilizer);
-        let water = self.find_range_mapping(&fertilizer, &self.fertilizer_to_water);
-        let light = self.find_range_mapping(&water, &self.water_to_light);
-        let temperature = self.find_range_mapping(&light, &self.light_to_temperature);
-        let humidity = self.find_range_mapping(&temperature, &self.temperature_to_humidity);
-        let location = self.find_range_mapping(&humidity, &self.humidity_to_location);
+    fn map_range(&self, targets: &IntervalSet) -> IntervalSet {
+        let (Some(min), Some(max)) = (targets.min(), targets.max()) else {
+            return IntervalSet::new();
+        };
+
+        let first = self.0.partition_point(|range| range.source_end() < min);
+        let (mapped, unmapped) = self.0[first..]
+            .iter()
+            .take_while(|range| range.source <= max)
+            .fold(
+                (IntervalSet::new(), targets.clone()),
+                |(mapped, unmapped), range| {
+                    let (inside, outside) = range.map_range(&unmapped);
+                    (mapped.union(&inside), outside)
+                },
+            );
+
+        mapped.union(&unmapped)
+    }
+
+    // Seeds reaching `targets`, kept as one piece per range plus the unmapped gaps, since the
+    // map is only monotonic within a single piece.
+    #[cfg(test)]
+    fn preimage_pieces(&self, targets: &IntervalSet) -> Vec<IntervalSet> {
+        let sources = self
+            .0
+            .iter()
+            .map(|range| range.source..range.source.saturating_add(range.length))
+            .collect::<IntervalSet>();
+
+        std::iter::once(targets.difference(&sources))
+            .chain(
+                self.0
+                    .iter()
+                    .map(|range| range.inverse().map_range(targets).0),
+            )
+            .collect()
+    }
+
+    #[cfg(test)]
+    fn preimage(&self, targets: &IntervalSet) -> IntervalSet {
+        self.preimage_pieces(targets)
+            .iter()
+            .fold(IntervalSet::new(), |preimage, piece| preimage.union(piece))
+    }
 
-        location
+    #[cfg(test)]
+    fn preimage_single(&self, target: u64) -> Vec<u64> {
+        self.preimage(&(target..target + 1).into())
+            .points()
+            .collect()
     }
 
-    fn get_seed_location(&self, seed: u64) -> u64 {
-        let soil = self.find_mapping(seed, &self.seed_to_soil);
-        let fertilizer = self.find_mapping(soil, &self.soil_to_fertilizer);
-        let water = self.find_mapping(fertilizer, &self.fertilizer_to_water);
-        let light = self.find_mapping(water, &self.water_to_light);
-        let temperature = self.find_mapping(light, &self.light_to_temperature);
-        let humidity = self.find_mapping(temperature, &self.temperature_to_humidity);
-        let location = self.find_mapping(humidity, &self.humidity_to_location);
+    // Between two consecutive breakpoints every preimage piece maps linearly onto the whole
+    // interval, so the first interval that reaches any seed holds the lowest location. Pieces
+    // are checked separately because neighbouring pieces can map to unrelated locations.
+    #[cfg(test)]
+    fn lowest_reachable(&self, seeds: &IntervalSet) -> Option<u64> {
+        let mut breakpoints = vec![0];
+        for range in &self.0 {
+            breakpoints.push(range.source);
+            breakpoints.push(range.destination);
+            breakpoints.extend(range.source.checked_add(range.length));
+            breakpoints.extend(range.destination.checked_add(range.length));
+        }
+        breakpoints.sort();
+        breakpoints.dedup();
+
+        let ends = breakpoints.iter().skip(1).copied().chain([u64::MAX]);
+        breakpoints.iter().zip(ends).find_map(|(&start, end)| {
+            self.preimage_pieces(&(start..end).into())
+                .iter()
+                .flat_map(|piece| {
+                    piece
+                        .intersection(seeds)
+                        .iter()
+                        .map(|reached| self.map_single(reached.start))
+                        .collect::<Vec<_>>()
+                })
+                .min()
+        })
+    }
+}
 
-        location
+impl std::fmt::Display for PiecewiseMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for range in &self.0 {
+            writeln!(f, "{} {} {}", range.destination, range.source, range.length)?;
+        }
+        Ok(())
     }
 }
 
-fn part02(input: &str) -> u64 {
-    let mut split = input.split("\n\n");
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AlmanacError {
+    InvalidHeader(String),
+    DuplicatedLink(String),
+    MissingLink(String),
+    CyclicLink(String),
+}
 
-    let values = split
-        .next()
-        .unwrap()
-        .trim_start_matches("seed: ")
-        .split_whitespace()
-        .filter_map(|seed| seed.parse::<u64>().ok())
-        .collect::<Vec<_>>();
+impl std::fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlmanacError::InvalidHeader(header) => write!(f, "Invalid map header: {header}"),
+            AlmanacError::DuplicatedLink(category) => {
+                write!(f, "Category {category} is mapped more than once")
+            }
+            AlmanacError::MissingLink(category) => write!(f, "Category {category} has no map"),
+            AlmanacError::CyclicLink(category) => {
+                write!(f, "Category {category} is reached twice")
+            }
+        }
+    }
+}
 
-    let seeds = values
-        .chunks(2)
-        .map(|pair| pair.into())
-        .collect::<Vec<Range>>();
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CategoryMap {
+    source: String,
+    destination: String,
+    ranges: Vec<MapRange>,
+}
 
-    let almanac = Alamanac::new(seeds);
+impl CategoryMap {
+    fn parse(raw: &str) -> Result<Self, AlmanacError> {
+        let (header, ranges) = raw.trim().split_once(':').unwrap_or((raw, ""));
+        let (source, destination) = header
+            .strip_suffix(" map")
+            .and_then(|name| name.split_once("-to-"))
+            .ok_or_else(|| AlmanacError::InvalidHeader(header.to_string()))?;
 
-    let almanac = split.fold(almanac, |mut almanac, raw| {
-        let (tp, ranges) = raw.trim().split_once(':').unwrap();
-        let tp = tp.split_whitespace().next().unwrap();
         let ranges = ranges
             .lines()
-            .filter_map(|raw| {
-                if !raw.is_empty() {
-                    Some::<MapRange>(raw.into())
-                } else {
-                    None
+            .filter(|raw| !raw.is_empty())
+            .map(MapRange::from)
+            .collect();
+
+        Ok(Self {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            ranges,
+        })
+    }
+
+    fn validate(&self) -> StageReport {
+        let mut issues = vec![];
+
+        let mut ranges = vec![];
+        for &range in &self.ranges {
+            if range.length == 0 {
+                issues.push(MapIssue::ZeroLength(range));
+            } else if range.overflows() {
+                issues.push(MapIssue::Overflow(range));
+            } else {
+                ranges.push(range);
+            }
+        }
+        ranges.sort_by_key(|range| range.source);
+
+        let mut covered = 0;
+        let mut gaps = 0;
+        let mut furthest: Option<MapRange> = None;
+        for &range in &ranges {
+            match furthest {
+                Some(previous) if range.source <= previous.source_end() => {
+                    issues.push(MapIssue::Overlap(previous, range));
+                    covered += range.source_end().saturating_sub(previous.source_end());
                 }
-            })
-            .collect::<Vec<_>>();
+                Some(previous) if range.source > previous.source_end().saturating_add(1) => {
+                    gaps += 1;
+                    covered += range.length;
+                }
+                _ => covered += range.length,
+            }
 
-        match tp {
-            "seed-to-soil" => almanac.seed_to_soil = ranges,
-            "soil-to-fertilizer" => almanac.soil_to_fertilizer = ranges,
-            "fertilizer-to-water" => almanac.fertilizer_to_water = ranges,
-            "water-to-light" => almanac.water_to_light = ranges,
-            "light-to-temperature" => almanac.light_to_temperature = ranges,
-            "temperature-to-humidity" => almanac.temperature_to_humidity = ranges,
-            "humidity-to-location" => almanac.humidity_to_location = ranges,
-            _ => unreachable!(),
+            if furthest.is_none_or(|previous| range.source_end() > previous.source_end()) {
+                furthest = Some(range);
+            }
         }
 
-        almanac
-    });
+        StageReport {
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            ranges: self.ranges.len(),
+            covered,
+            gaps,
+            issues,
+        }
+    }
+
+    fn normalize(&mut self) {
+        self.ranges.retain(|range| range.length > 0);
+        self.ranges.sort_by_key(|range| range.source);
+
+        let mut merged: Vec<MapRange> = vec![];
+        for &range in &self.ranges {
+            match merged.last_mut() {
+                Some(last)
+                    if last.source + last.length == range.source
+                        && last.destination + last.length == range.destination =>
+                {
+                    last.length += range.length
+                }
+                _ => merged.push(range),
+            }
+        }
 
-    almanac.find_lowest_ranged_location()
+        self.ranges = merged;
+    }
 }
 
-fn part01(input: &str) -> u64 {
-    let mut split = input.split("\n\n");
-    let seeds = split
-        .next()
-        .unwrap()
-        .trim_start_matches("seed: ")
-        .split_whitespace()
-        .filter_map(|seed| seed.parse::<u64>().ok().and_then(|s| Some(s.into())))
-        .collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapIssue {
+    ZeroLength(MapRange),
+    Overflow(MapRange),
+    Overlap(MapRange, MapRange),
+}
 
-    let almanac = Alamanac::new(seeds);
+impl std::fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format =
+            |range: &MapRange| format!("{} {} {}", range.destination, range.source, range.length);
 
-    let almanac = split.fold(almanac, |mut almanac, raw| {
-        let (tp, ranges) = raw.trim().split_once(':').unwrap();
-        let tp = tp.split_whitespace().next().unwrap();
-        let ranges = ranges
-            .lines()
-            .filter_map(|raw| {
-                if !raw.is_empty() {
-                    Some::<MapRange>(raw.into())
-                } else {
-                    None
-                }
-            })
+        match self {
+            MapIssue::ZeroLength(range) => write!(f, "Zero length: {}", format(range)),
+            MapIssue::Overflow(range) => write!(f, "Overflow: {}", format(range)),
+            MapIssue::Overlap(a, b) => write!(f, "Overlap: {} and {}", format(a), format(b)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StageReport {
+    source: String,
+    destination: String,
+    ranges: usize,
+    covered: u64,
+    gaps: usize,
+    issues: Vec<MapIssue>,
+}
+
+impl std::fmt::Display for StageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-to-{}: {} ranges, {} covered, {} gaps, {} issues",
+            self.source,
+            self.destination,
+            self.ranges,
+            self.covered,
+            self.gaps,
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n  {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Alamanac {
+    seeds: Vec<u64>,
+    maps: HashMap<String, CategoryMap>,
+}
+
+impl Alamanac {
+    fn parse(input: &str) -> Result<Self, AlmanacError> {
+        let mut split = input.split("\n\n");
+
+        let seeds = split
+            .next()
+            .unwrap()
+            .trim_start_matches("seeds:")
+            .split_whitespace()
+            .filter_map(|seed| seed.parse::<u64>().ok())
+            .collect();
+
+        let mut maps = HashMap::new();
+        for raw in split {
+            let map = CategoryMap::parse(raw)?;
+            if maps.contains_key(&map.source) {
+                return Err(AlmanacError::DuplicatedLink(map.source));
+            }
+            maps.insert(map.source.clone(), map);
+        }
+
+        Ok(Self { seeds, maps })
+    }
+
+    fn validate(&self) -> Vec<StageReport> {
+        let mut reports = self
+            .maps
+            .values()
+            .map(|map| map.validate())
             .collect::<Vec<_>>();
 
-        match tp {
-            "seed-to-soil" => almanac.seed_to_soil = ranges,
-            "soil-to-fertilizer" => almanac.soil_to_fertilizer = ranges,
-            "fertilizer-to-water" => almanac.fertilizer_to_water = ranges,
-            "water-to-light" => almanac.water_to_light = ranges,
-            "light-to-temperature" => almanac.light_to_temperature = ranges,
-            "temperature-to-humidity" => almanac.temperature_to_humidity = ranges,
-            "humidity-to-location" => almanac.humidity_to_location = ranges,
-            _ => unreachable!(),
+        reports.sort_by(|a, b| a.source.cmp(&b.source));
+        reports
+    }
+
+    fn normalized(mut self) -> Self {
+        self.maps.values_mut().for_each(|map| map.normalize());
+        self
+    }
+
+    fn seeds(&self) -> IntervalSet {
+        self.seeds.iter().map(|&seed| seed..seed + 1).collect()
+    }
+
+    fn seed_ranges(&self) -> IntervalSet {
+        self.seeds
+            .chunks(2)
+            .map(|pair| pair[0]..pair[0] + pair[1])
+            .collect()
+    }
+
+    fn route(&self, from: &str, to: &str) -> Result<Vec<&CategoryMap>, AlmanacError> {
+        let mut route = vec![];
+        let mut visited = HashSet::new();
+        let mut current = from;
+
+        while current != to {
+            if !visited.insert(current) {
+                return Err(AlmanacError::CyclicLink(current.to_string()));
+            }
+
+            let map = self
+                .maps
+                .get(current)
+                .ok_or_else(|| AlmanacError::MissingLink(current.to_string()))?;
+
+            route.push(map);
+            current = &map.destination;
         }
 
-        almanac
-    });
+        Ok(route)
+    }
 
-    almanac.find_lowest_location()
+    fn flatten(&self, from: &str, to: &str) -> Result<PiecewiseMap, AlmanacError> {
+        Ok(self
+            .route(from, to)?
+            .into_iter()
+            .fold(PiecewiseMap::default(), |flattened, map| {
+                flattened.compose(&PiecewiseMap::new(map.ranges.clone()))
+            }))
+    }
+
+    fn find_lowest_location(&self) -> Result<u64, AlmanacError> {
+        let flattened = self.flatten("seed", "location")?;
+
+        Ok(self
+            .seeds()
+            .points()
+            .map(|seed| flattened.map_single(seed))
+            .min()
+            .unwrap())
+    }
+
+    fn find_lowest_ranged_location(&self) -> Result<u64, AlmanacError> {
+        let flattened = self.flatten("seed", "location")?;
+
+        Ok(flattened.map_range(&self.seed_ranges()).min().unwrap())
+    }
+
+    #[cfg(test)]
+    fn find_lowest_inverse_location(&self) -> Result<u64, AlmanacError> {
+        let flattened = self.flatten("seed", "location")?;
+
+        Ok(flattened.lowest_reachable(&self.seed_ranges()).unwrap())
+    }
+
+    #[cfg(test)]
+    fn seeds_landing_in(
+        &self,
+        locations: std::ops::Range<u64>,
+    ) -> Result<IntervalSet, AlmanacError> {
+        let flattened = self.flatten("seed", "location")?;
+
+        Ok(flattened
+            .preimage(&locations.into())
+            .intersection(&self.seed_ranges()))
+    }
+}
+
+fn part02(input: &str) -> u64 {
+    Alamanac::parse(input)
+        .unwrap()
+        .normalized()
+        .find_lowest_ranged_location()
+        .unwrap()
+}
+
+fn part01(input: &str) -> u64 {
+    Alamanac::parse(input)
+        .unwrap()
+        .normalized()
+        .find_lowest_location()
+        .unwrap()
 }
 
 fn main() {
     let input = include_str!("../input/day05.input");
+    for report in Alamanac::parse(input).unwrap().validate() {
+        if !report.issues.is_empty() {
+            println!("{report}");
+        }
+    }
     println!("Part 01: {}", part01(input));
     println!("Part 02: {}", part02(input));
 }
@@ -308,226 +563,289 @@ fn main() {
 mod test {
     use super::*;
 
-    #[test]
-    fn split_outside() {
-        let map_range = MapRange {
-            destination: 30,
-            source: 8,
-            length: 7,
-        };
+    const INPUT: &str = "seeds: 79 14 55 13
 
-        let after_range = Range {
-            start: 15,
-            lenght: 3,
-        };
-        let before_range = Range {
-            start: 5,
-            lenght: 3,
-        };
-        assert_eq!(map_range.split(after_range), None);
-        assert_eq!(map_range.split(before_range), None);
-    }
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
 
     #[test]
-    fn split_part_inside() {
+    fn map_range_outside() {
         let map_range = MapRange {
             destination: 30,
             source: 8,
             length: 7,
         };
-        let part_inside_range = Range {
-            start: 13,
-            lenght: 7,
-        };
+
+        let after_range = IntervalSet::from(15..18);
+        let before_range = IntervalSet::from(5..8);
         assert_eq!(
-            map_range.split(part_inside_range),
-            Some((
-                Range {
-                    start: 13,
-                    lenght: 2,
-                },
-                vec![Range {
-                    start: 15,
-                    lenght: 5,
-                },]
-            ))
+            map_range.map_range(&after_range),
+            (IntervalSet::new(), after_range)
+        );
+        assert_eq!(
+            map_range.map_range(&before_range),
+            (IntervalSet::new(), before_range)
         );
     }
 
     #[test]
-    fn split_full_inside() {
+    fn map_range_part_inside() {
         let map_range = MapRange {
             destination: 30,
             source: 8,
             length: 7,
         };
 
-        let full_inside_range = Range {
-            start: 10,
-            lenght: 3,
-        };
-
         assert_eq!(
-            map_range.split(full_inside_range),
-            Some((
-                Range {
-                    start: 10,
-                    lenght: 3,
-                },
-                vec![],
-            ))
+            map_range.map_range(&(13..20).into()),
+            (IntervalSet::from(35..37), IntervalSet::from(15..20))
+        );
+        assert_eq!(
+            map_range.map_range(&(5..20).into()),
+            (
+                IntervalSet::from(30..37),
+                IntervalSet::from_iter([5..8, 15..20])
+            )
         );
     }
 
     #[test]
-    fn map_range_outside() {
+    fn map_range_full_inside() {
         let map_range = MapRange {
             destination: 30,
             source: 8,
             length: 7,
         };
 
-        let after_range = Range {
-            start: 15,
-            lenght: 3,
-        };
-        let before_range = Range {
-            start: 5,
-            lenght: 3,
-        };
-        assert_eq!(map_range.map_range(after_range), (None, vec![after_range]));
         assert_eq!(
-            map_range.map_range(before_range),
-            (None, vec![before_range])
+            map_range.map_range(&(10..13).into()),
+            (IntervalSet::from(32..35), IntervalSet::new())
         );
     }
 
     #[test]
-    fn map_range_part_inside() {
-        let map_range = MapRange {
-            destination: 30,
-            source: 8,
-            length: 7,
-        };
-        let part_inside_range = Range {
-            start: 13,
-            lenght: 7,
-        };
+    fn part01() {
+        assert_eq!(super::part01(INPUT), 35);
+    }
+
+    #[test]
+    fn part02() {
+        assert_eq!(super::part02(INPUT), 46);
+    }
+
+    #[test]
+    fn route() {
+        let almanac = Alamanac::parse(INPUT).unwrap();
+        let route = almanac.route("soil", "humidity").unwrap();
+        let flattened = almanac.flatten("soil", "humidity").unwrap();
+
         assert_eq!(
-            map_range.map_range(part_inside_range),
-            (
-                Some(Range {
-                    start: 35,
-                    lenght: 2,
-                }),
-                vec![Range {
-                    start: 15,
-                    lenght: 5,
-                },]
-            )
+            route
+                .iter()
+                .map(|map| map.destination.as_str())
+                .collect::<Vec<_>>(),
+            vec!["fertilizer", "water", "light", "temperature", "humidity"]
+        );
+        assert_eq!(flattened.map_single(81), 78);
+        assert_eq!(
+            almanac.route("location", "seed").unwrap_err(),
+            AlmanacError::MissingLink("location".to_string())
         );
     }
 
     #[test]
-    fn map_range_full_inside() {
-        let map_range = MapRange {
-            destination: 30,
-            source: 8,
-            length: 7,
-        };
+    fn route_errors() {
+        let input = "seeds: 1 2
 
-        let full_inside_range = Range {
-            start: 10,
-            lenght: 3,
-        };
+a-to-b map:
+1 2 3
 
+b-to-a map:
+2 1 3";
+        let almanac = Alamanac::parse(input).unwrap();
         assert_eq!(
-            map_range.map_range(full_inside_range),
-            (
-                Some(Range {
-                    start: 32,
-                    lenght: 3,
-                }),
-                vec![],
-            )
+            almanac.route("a", "c").unwrap_err(),
+            AlmanacError::CyclicLink("a".to_string())
+        );
+
+        let input = "seeds: 1 2
+
+a-to-b map:
+1 2 3
+
+a-to-c map:
+2 1 3";
+        assert_eq!(
+            Alamanac::parse(input).unwrap_err(),
+            AlmanacError::DuplicatedLink("a".to_string())
+        );
+
+        let input = "seeds: 1 2
+
+a-b map:
+1 2 3";
+        assert_eq!(
+            Alamanac::parse(input).unwrap_err(),
+            AlmanacError::InvalidHeader("a-b map".to_string())
         );
     }
 
     #[test]
-    fn part01() {
-        let input = "seeds: 79 14 55 13
+    fn compose() {
+        let first = PiecewiseMap::new(vec!["50 98 2".into(), "52 50 48".into()]);
+        let second = PiecewiseMap::new(vec!["0 15 37".into(), "37 52 2".into(), "39 0 15".into()]);
+        let composed = first.compose(&second);
 
-seed-to-soil map:
-50 98 2
-52 50 48
+        assert_eq!(
+            composed.to_string(),
+            "39 0 15\n0 15 35\n37 50 2\n54 52 46\n35 98 2\n"
+        );
 
-soil-to-fertilizer map:
-0 15 37
-37 52 2
-39 0 15
+        for seed in 0..120 {
+            assert_eq!(
+                composed.map_single(seed),
+                second.map_single(first.map_single(seed))
+            );
+        }
 
-fertilizer-to-water map:
-49 53 8
-0 11 42
-42 0 7
-57 7 4
+        assert_eq!(
+            composed.map_range(&(45..55).into()),
+            IntervalSet::from_iter([30..35, 37..39, 54..57])
+        );
+    }
 
-water-to-light map:
-88 18 7
-18 25 70
+    #[test]
+    fn preimage() {
+        let almanac = Alamanac::parse(INPUT).unwrap();
+        let flattened = almanac.flatten("seed", "location").unwrap();
+
+        for location in 0..120 {
+            let seeds = flattened.preimage_single(location);
+            assert_eq!(
+                seeds,
+                (0..120)
+                    .filter(|&seed| flattened.map_single(seed) == location)
+                    .collect::<Vec<_>>()
+            );
+        }
 
-light-to-temperature map:
-45 77 23
-81 45 19
-68 64 13
+        assert_eq!(flattened.preimage_single(82), vec![79]);
+        assert_eq!(
+            almanac.find_lowest_inverse_location(),
+            almanac.find_lowest_ranged_location()
+        );
+        assert_eq!(
+            almanac.seeds_landing_in(46..47),
+            Ok(IntervalSet::from(82..83))
+        );
+    }
 
-temperature-to-humidity map:
-0 69 1
-1 0 69
+    #[test]
+    fn inverse_adjacent_pieces() {
+        // Seed 9 goes through the range and seed 10 through the gap after it, so their
+        // preimage pieces touch but map to unrelated locations.
+        let input = "seeds: 9 2
 
-humidity-to-location map:
-60 56 37
-56 93 4";
+seed-to-location map:
+10 0 10";
+        let almanac = Alamanac::parse(input).unwrap();
 
-        assert_eq!(super::part01(input), 35);
+        assert_eq!(almanac.find_lowest_ranged_location(), Ok(10));
+        assert_eq!(almanac.find_lowest_inverse_location(), Ok(10));
     }
 
     #[test]
-    fn part02() {
-        let input = "seeds: 79 14 55 13
+    fn validate() {
+        let input = "seeds: 1 2
 
-seed-to-soil map:
-50 98 2
-52 50 48
+a-to-b map:
+10 0 5
+20 3 4
+0 30 0
+1 18446744073709551615 2
 
-soil-to-fertilizer map:
-0 15 37
-37 52 2
-39 0 15
+b-to-c map:
+50 10 5
+60 30 10
+55 15 5
 
-fertilizer-to-water map:
-49 53 8
-0 11 42
-42 0 7
-57 7 4
+c-to-d map:
+0 18446744073709551615 1
+5 0 3";
 
-water-to-light map:
-88 18 7
-18 25 70
+        let mut almanac = Alamanac::parse(input).unwrap();
+        let reports = almanac.validate();
 
-light-to-temperature map:
-45 77 23
-81 45 19
-68 64 13
-
-temperature-to-humidity map:
-0 69 1
-1 0 69
+        assert_eq!(
+            reports[0].to_string(),
+            "a-to-b: 4 ranges, 7 covered, 0 gaps, 3 issues
+  Zero length: 0 30 0
+  Overflow: 1 18446744073709551615 2
+  Overlap: 10 0 5 and 20 3 4"
+        );
+        assert_eq!(
+            reports[1].to_string(),
+            "b-to-c: 3 ranges, 20 covered, 1 gaps, 0 issues"
+        );
+        assert_eq!(
+            reports[2].to_string(),
+            "c-to-d: 2 ranges, 4 covered, 1 gaps, 0 issues"
+        );
 
-humidity-to-location map:
-60 56 37
-56 93 4";
+        let flattened = almanac.flatten("c", "d").unwrap();
+        assert_eq!(flattened.map_single(u64::MAX), 0);
+        assert_eq!(flattened.map_single(1), 6);
+        // The seed u64::MAX itself can't be held by an IntervalSet, so it's missing here.
+        assert_eq!(
+            flattened.preimage(&(0..10).into()),
+            IntervalSet::from(0..10)
+        );
+        assert_eq!(flattened.lowest_reachable(&(0..10).into()), Some(3));
 
-        assert_eq!(super::part02(input), 46);
+        almanac = almanac.normalized();
+        assert_eq!(
+            almanac.maps["b"].ranges,
+            vec![
+                MapRange {
+                    destination: 50,
+                    source: 10,
+                    length: 10
+                },
+                MapRange {
+                    destination: 60,
+                    source: 30,
+                    length: 10
+                },
+            ]
+        );
     }
 }
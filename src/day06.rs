@@ -1,7 +1,133 @@
-fn is_hold_time_enough(hold_time: u64, time_limit: u64, max_distance: u64) -> bool {
+use std::ops::RangeInclusive;
+
+use advent_of_code::interval::IntervalSet;
+
+fn is_hold_time_enough(hold_time: u128, time_limit: u128, max_distance: u128) -> bool {
     (time_limit - hold_time) * hold_time > max_distance
 }
 
+fn charged_hold_times(
+    rate: u64,
+    time_limit: u64,
+    max_distance: u64,
+) -> Option<RangeInclusive<u64>> {
+    let (rate, time) = (rate as u128, time_limit as u128);
+    if rate == 0 {
+        return None;
+    }
+
+    // rate * h * (t - h) > d is the same as h * (t - h) > d / rate, rounded down.
+    let distance = max_distance as u128 / rate;
+
+    // Winning hold times are the integers strictly between the roots of h² - th + d = 0.
+    let discriminant = (time * time).checked_sub(4 * distance)?;
+    let mut min = (time - discriminant.isqrt()) / 2;
+
+    while min <= time / 2 && !is_hold_time_enough(min, time, distance) {
+        min += 1;
+    }
+    while min > 0 && is_hold_time_enough(min - 1, time, distance) {
+        min -= 1;
+    }
+
+    if min > time / 2 {
+        return None;
+    }
+
+    let max = time - min;
+    Some(min as u64..=max as u64)
+}
+
+fn count_winning_hold_times(time_limit: u64, max_distance: u64) -> u64 {
+    Boat::Standard
+        .winning_hold_times(time_limit, max_distance)
+        .len()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Boat {
+    Standard,
+    #[allow(dead_code)]
+    Charging { rate: u64 },
+    #[allow(dead_code)]
+    Capped { rate: u64, max_speed: u64 },
+    #[allow(dead_code)]
+    Decaying { rate: u64, decay: u64 },
+    #[allow(dead_code)]
+    Cooldown { rate: u64, cooldown: u64 },
+}
+
+impl Boat {
+    fn speed(&self, hold_time: u64) -> u128 {
+        let hold_time = hold_time as u128;
+        match *self {
+            Boat::Standard => hold_time,
+            Boat::Charging { rate } | Boat::Cooldown { rate, .. } => rate as u128 * hold_time,
+            Boat::Capped { rate, max_speed } => (rate as u128 * hold_time).min(max_speed as u128),
+            Boat::Decaying { rate, decay } => {
+                let (rate, decay) = (rate as u128, decay as u128);
+                let charging = if decay == 0 {
+                    hold_time
+                } else {
+                    hold_time.min(rate.div_ceil(decay))
+                };
+                charging * rate - decay * charging * charging.saturating_sub(1) / 2
+            }
+        }
+    }
+
+    fn distance(&self, hold_time: u64, time_limit: u64) -> u128 {
+        let cooldown = match *self {
+            Boat::Cooldown { cooldown, .. } => cooldown,
+            _ => 0,
+        };
+
+        let moving_time = time_limit
+            .saturating_sub(hold_time)
+            .saturating_sub(cooldown);
+        self.speed(hold_time) * moving_time as u128
+    }
+
+    fn winning_hold_times(&self, time_limit: u64, max_distance: u64) -> IntervalSet {
+        let to_set = |hold_times: Option<RangeInclusive<u64>>| {
+            hold_times.map_or(IntervalSet::new(), |hold_times| {
+                IntervalSet::from(*hold_times.start()..hold_times.end() + 1)
+            })
+        };
+
+        match *self {
+            Boat::Standard => to_set(charged_hold_times(1, time_limit, max_distance)),
+            Boat::Charging { rate } => to_set(charged_hold_times(rate, time_limit, max_distance)),
+            Boat::Cooldown { rate, cooldown } => to_set(charged_hold_times(
+                rate,
+                time_limit.saturating_sub(cooldown),
+                max_distance,
+            )),
+            Boat::Capped { rate, max_speed } if rate > 0 && max_speed > 0 => {
+                // Once the cap is reached, holding any longer only loses moving time.
+                let capped_at = max_speed.div_ceil(rate);
+                let charging = to_set(charged_hold_times(rate, time_limit, max_distance))
+                    .intersection(&(0..capped_at).into());
+
+                let last = time_limit
+                    .checked_sub(max_distance / max_speed)
+                    .and_then(|time| time.checked_sub(1));
+                let capped = last.map_or(IntervalSet::new(), |last| (capped_at..last + 1).into());
+
+                charging.union(&capped)
+            }
+            _ => self.search_hold_times(time_limit, max_distance),
+        }
+    }
+
+    fn search_hold_times(&self, time_limit: u64, max_distance: u64) -> IntervalSet {
+        (0..=time_limit)
+            .filter(|&hold_time| self.distance(hold_time, time_limit) > max_distance as u128)
+            .map(|hold_time| hold_time..hold_time + 1)
+            .collect()
+    }
+}
+
 fn part02(input: &str) -> u64 {
     let (time, distance) = input.split_once('\n').unwrap();
     let time = time
@@ -20,19 +146,7 @@ fn part02(input: &str) -> u64 {
         .parse::<u64>()
         .unwrap();
 
-    let min = (1..time)
-        .into_iter()
-        .find(|&hold_time| is_hold_time_enough(hold_time, time, distance))
-        .unwrap();
-    let max = (1..time)
-        .rev()
-        .into_iter()
-        .find(|&hold_time| is_hold_time_enough(hold_time, time, distance))
-        .unwrap();
-
-    let max = max + 1;
-
-    max - min
+    count_winning_hold_times(time, distance)
 }
 
 fn part01(input: &str) -> u64 {
@@ -49,22 +163,8 @@ fn part01(input: &str) -> u64 {
 
     times
         .zip(distance)
-        .map(|(time, distance)| {
-            let min = (1..time)
-                .into_iter()
-                .find(|&hold_time| is_hold_time_enough(hold_time, time, distance))
-                .unwrap();
-            let max = (1..time)
-                .rev()
-                .into_iter()
-                .find(|&hold_time| is_hold_time_enough(hold_time, time, distance))
-                .unwrap();
-
-            let max = max + 1;
-
-            max - min
-        })
-        .fold(1, |acc, count| acc * count)
+        .map(|(time, distance)| count_winning_hold_times(time, distance))
+        .product()
 }
 
 fn main() {
@@ -90,4 +190,59 @@ Distance:  9  40  200";
 
         assert_eq!(super::part02(input), 71503);
     }
+
+    #[test]
+    fn winning_hold_times() {
+        assert_eq!(super::charged_hold_times(1, 7, 9), Some(2..=5));
+        assert_eq!(super::charged_hold_times(1, 30, 200), Some(11..=19));
+        assert_eq!(super::charged_hold_times(1, 4, 4), None);
+        assert_eq!(super::charged_hold_times(1, 4, 5), None);
+        assert_eq!(super::charged_hold_times(1, 4, 3), Some(2..=2));
+        assert_eq!(super::charged_hold_times(1, 10, 0), Some(1..=9));
+        assert_eq!(
+            super::charged_hold_times(1, u64::MAX, u64::MAX),
+            Some(2..=u64::MAX - 2)
+        );
+    }
+
+    #[test]
+    fn boat_models() {
+        use super::Boat;
+
+        let boats = [
+            Boat::Standard,
+            Boat::Charging { rate: 3 },
+            Boat::Capped {
+                rate: 2,
+                max_speed: 9,
+            },
+            Boat::Decaying { rate: 5, decay: 2 },
+            Boat::Cooldown {
+                rate: 2,
+                cooldown: 4,
+            },
+        ];
+
+        for boat in boats {
+            for time in 0..40 {
+                for distance in (0..300).step_by(7) {
+                    assert_eq!(
+                        boat.winning_hold_times(time, distance),
+                        boat.search_hold_times(time, distance),
+                        "{boat:?} {time} {distance}"
+                    );
+                }
+            }
+        }
+
+        let boat = Boat::Capped {
+            rate: 2,
+            max_speed: 9,
+        };
+        assert_eq!(boat.winning_hold_times(30, 200), (4..8).into());
+
+        let boat = Boat::Decaying { rate: 5, decay: 2 };
+        assert_eq!(boat.speed(10), 9);
+        assert_eq!(boat.winning_hold_times(7, 20), (1..5).into());
+    }
 }
@@ -1,313 +1,328 @@
-use std::{collections::HashMap, fmt::Display};
-
-#[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
-#[repr(u8)]
-enum CamelCard {
-    #[default]
-    Joker = 1,
-    Two = 2,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    T,
-    J,
-    Q,
-    K,
-    A,
-}
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
 
-impl Display for CamelCard {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CamelCard::Two => write!(f, "{}", '2'),
-            CamelCard::Three => write!(f, "{}", '3'),
-            CamelCard::Four => write!(f, "{}", '4'),
-            CamelCard::Five => write!(f, "{}", '5'),
-            CamelCard::Six => write!(f, "{}", '6'),
-            CamelCard::Seven => write!(f, "{}", '7'),
-            CamelCard::Eight => write!(f, "{}", '8'),
-            CamelCard::Nine => write!(f, "{}", '9'),
-            CamelCard::T => write!(f, "{}", 'T'),
-            CamelCard::J => write!(f, "{}", 'J'),
-            CamelCard::Q => write!(f, "{}", 'Q'),
-            CamelCard::K => write!(f, "{}", 'K'),
-            CamelCard::A => write!(f, "{}", 'A'),
-            CamelCard::Joker => write!(f, "{}", 'J'),
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct HandType(Vec<u8>);
+
+impl HandType {
+    fn from_counts(mut counts: Vec<u8>) -> Self {
+        counts.sort_by(|a, b| b.cmp(a));
+        Self(counts)
     }
-}
 
-impl CamelCard {
-    fn new_with_joker(char: char) -> Self {
-        match char {
-            '2' => Self::Two,
-            '3' => Self::Three,
-            '4' => Self::Four,
-            '5' => Self::Five,
-            '6' => Self::Six,
-            '7' => Self::Seven,
-            '8' => Self::Eight,
-            '9' => Self::Nine,
-            'T' => Self::T,
-            'J' => Self::Joker,
-            'Q' => Self::Q,
-            'K' => Self::K,
-            'A' => Self::A,
-            _ => unreachable!(),
+    fn partitions(size: u8, max: u8) -> Vec<Vec<u8>> {
+        if size == 0 {
+            return vec![vec![]];
         }
+
+        (1..=size.min(max))
+            .flat_map(|first| {
+                Self::partitions(size - first, first)
+                    .into_iter()
+                    .map(move |mut rest| {
+                        rest.insert(0, first);
+                        rest
+                    })
+            })
+            .collect()
     }
 
-    fn new(char: char) -> Self {
-        match char {
-            '2' => Self::Two,
-            '3' => Self::Three,
-            '4' => Self::Four,
-            '5' => Self::Five,
-            '6' => Self::Six,
-            '7' => Self::Seven,
-            '8' => Self::Eight,
-            '9' => Self::Nine,
-            'T' => Self::T,
-            'J' => Self::J,
-            'Q' => Self::Q,
-            'K' => Self::K,
-            'A' => Self::A,
-            _ => unreachable!(),
-        }
+    fn all(hand_size: usize) -> Vec<HandType> {
+        let mut all = Self::partitions(hand_size as u8, hand_size as u8)
+            .into_iter()
+            .map(HandType)
+            .collect::<Vec<_>>();
+        all.sort();
+        all
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, Ord)]
-enum Hand {
-    HighCard([CamelCard; 5]),
-    OnePair([CamelCard; 5]),
-    TwoPair([CamelCard; 5]),
-    ThreeOfAKind([CamelCard; 5]),
-    FullHouse([CamelCard; 5]),
-    FourOfAKind([CamelCard; 5]),
-    FiveOfAKind([CamelCard; 5]),
+impl Display for HandType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.as_slice() {
+            [5] => write!(f, "FiveOfAKind"),
+            [4, 1] => write!(f, "FourOfAKind"),
+            [3, 2] => write!(f, "FullHouse"),
+            [3, 1, 1] => write!(f, "ThreeOfAKind"),
+            [2, 2, 1] => write!(f, "TwoPair"),
+            [2, 1, 1, 1] => write!(f, "OnePair"),
+            [1, 1, 1, 1, 1] => write!(f, "HighCard"),
+            counts => {
+                let counts = counts.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", counts.join("-"))
+            }
+        }
+    }
 }
 
-#[derive(Default)]
-enum Joker {
-    #[default]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WildUpgrade {
     Ignore,
-    Use,
+    JoinLargest,
+    #[allow(dead_code)]
+    BestType,
 }
 
-impl Hand {
-    fn parse(value: &str, joker: Joker) -> Self {
-        let cards = value.chars().take(5).enumerate().fold(
-            [CamelCard::default(); 5],
-            |mut acc, (idx, c)| {
-                let card = match joker {
-                    Joker::Ignore => CamelCard::new(c),
-                    Joker::Use => CamelCard::new_with_joker(c),
-                };
-                acc[idx] = card;
-                acc
-            },
-        );
-
-        Hand::new(cards)
-    }
-
-    fn new(cards: [CamelCard; 5]) -> Self {
-        let card_map = cards.iter().fold(HashMap::new(), |mut map, &card| {
-            *map.entry(card).or_insert(0u32) += 1;
-            map
-        });
-
-        let (unique_cards, max_count, min_count) = (
-            card_map.len(),
-            card_map.values().max().unwrap(),
-            card_map.values().min().unwrap(),
-        );
-
-        let hand = match (unique_cards, max_count, min_count) {
-            (1, 5, _) => Self::FiveOfAKind(cards),
-            (2, 4, 1) => Self::FourOfAKind(cards),
-            (2, 3, 2) => Self::FullHouse(cards),
-            (3, 3, 1) => Self::ThreeOfAKind(cards),
-            (3, 2, 1) => Self::TwoPair(cards),
-            (4, 2, 1) => Self::OnePair(cards),
-            _ => Self::HighCard(cards),
-        };
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rules {
+    ranks: Vec<char>,
+    hand_size: usize,
+    wild: Option<char>,
+    upgrade: WildUpgrade,
+    precedence: Vec<HandType>,
+}
 
-        if hand.joker_count() > 0 {
-            hand.upgrade()
-        } else {
-            hand
+impl Rules {
+    fn standard() -> Self {
+        Self {
+            ranks: "23456789TJQKA".chars().collect(),
+            hand_size: 5,
+            wild: None,
+            upgrade: WildUpgrade::Ignore,
+            precedence: HandType::all(5),
         }
     }
 
-    fn cards(&self) -> &[CamelCard; 5] {
-        match self {
-            Hand::HighCard(cards) => cards,
-            Hand::OnePair(cards) => cards,
-            Hand::TwoPair(cards) => cards,
-            Hand::ThreeOfAKind(cards) => cards,
-            Hand::FourOfAKind(cards) => cards,
-            Hand::FiveOfAKind(cards) => cards,
-            Hand::FullHouse(cards) => cards,
+    fn jokers() -> Self {
+        Self {
+            ranks: "J23456789TQKA".chars().collect(),
+            wild: Some('J'),
+            upgrade: WildUpgrade::JoinLargest,
+            ..Self::standard()
         }
     }
 
-    fn rank(&self) -> u32 {
-        match self {
-            Hand::HighCard(_) => 1,
-            Hand::OnePair(_) => 2,
-            Hand::TwoPair(_) => 3,
-            Hand::ThreeOfAKind(_) => 4,
-            Hand::FullHouse(_) => 5,
-            Hand::FourOfAKind(_) => 6,
-            Hand::FiveOfAKind(_) => 7,
-        }
+    fn strength(&self, card: char) -> u8 {
+        self.ranks
+            .iter()
+            .position(|&rank| rank == card)
+            .unwrap_or_else(|| panic!("Card {card} isn't part of the deck")) as u8
     }
 
-    fn joker_count(&self) -> usize {
-        self.cards()
-            .into_iter()
-            .filter(|c| matches!(c, CamelCard::Joker))
-            .count()
+    fn type_strength(&self, hand_type: &HandType) -> usize {
+        self.precedence
+            .iter()
+            .position(|t| t == hand_type)
+            .unwrap_or_else(|| panic!("Hand type {hand_type} has no precedence"))
     }
 
-    fn upgrade(self) -> Hand {
-        if self.joker_count() == 5 {
-            return self;
-        }
-
-        let card_map = self
-            .cards()
-            .into_iter()
-            .filter(|c| !matches!(c, CamelCard::Joker))
-            .fold(HashMap::new(), |mut map, &card| {
-                *map.entry(card).or_insert(0u32) += 1;
-                map
-            });
-
-        let (best_card, _) = card_map
-            .into_iter()
-            .max_by(|(c_a, cnt_a), (c_b, cnt_b)| {
-                if cnt_a == cnt_b {
-                    c_a.cmp(c_b)
-                } else {
-                    cnt_a.cmp(cnt_b)
-                }
+    fn classify(&self, cards: &str, substitute: Option<char>) -> HandType {
+        let counts = cards
+            .chars()
+            .map(|card| match (self.wild, substitute) {
+                (Some(wild), Some(substitute)) if card == wild => substitute,
+                _ => card,
             })
-            .unwrap();
-
-        let new_cards = self.cards().into_iter().enumerate().fold(
-            [Default::default(); 5],
-            |mut cards, (idx, &card)| {
-                cards[idx] = if matches!(card, CamelCard::Joker) {
-                    best_card
-                } else {
-                    card
-                };
+            .fold(vec![0u8; self.ranks.len()], |mut counts, card| {
+                counts[self.strength(card) as usize] += 1;
+                counts
+            });
 
-                cards
-            },
-        );
+        HandType::from_counts(counts.into_iter().filter(|&count| count > 0).collect())
+    }
 
-        let updated = Hand::new(new_cards);
+    fn substitute(&self, cards: &str) -> Option<char> {
+        let wild = self.wild?;
+        if !cards.contains(wild) {
+            return None;
+        }
 
-        match updated {
-            Hand::OnePair(_) => Self::OnePair(self.take_cards()),
-            Hand::TwoPair(_) => Self::TwoPair(self.take_cards()),
-            Hand::ThreeOfAKind(_) => Self::ThreeOfAKind(self.take_cards()),
-            Hand::FullHouse(_) => Self::FullHouse(self.take_cards()),
-            Hand::FourOfAKind(_) => Self::FourOfAKind(self.take_cards()),
-            Hand::FiveOfAKind(_) => Self::FiveOfAKind(self.take_cards()),
-            _ => unreachable!(),
+        match self.upgrade {
+            WildUpgrade::Ignore => None,
+            WildUpgrade::JoinLargest => cards
+                .chars()
+                .filter(|&card| card != wild)
+                .fold(HashMap::new(), |mut map, card| {
+                    *map.entry(card).or_insert(0u32) += 1;
+                    map
+                })
+                .into_iter()
+                .max_by_key(|&(card, count)| (count, self.strength(card)))
+                .map(|(card, _)| card),
+            WildUpgrade::BestType => self
+                .ranks
+                .iter()
+                .copied()
+                .filter(|&card| card != wild)
+                .max_by_key(|&card| {
+                    let hand_type = self.classify(cards, Some(card));
+                    (self.type_strength(&hand_type), self.strength(card))
+                }),
         }
     }
+}
 
-    fn take_cards(self) -> [CamelCard; 5] {
-        match self {
-            Hand::HighCard(cards) => cards,
-            Hand::OnePair(cards) => cards,
-            Hand::TwoPair(cards) => cards,
-            Hand::ThreeOfAKind(cards) => cards,
-            Hand::FullHouse(cards) => cards,
-            Hand::FourOfAKind(cards) => cards,
-            Hand::FiveOfAKind(cards) => cards,
+#[derive(Debug, Clone)]
+struct Hand {
+    labels: String,
+    cards: Vec<u8>,
+    hand_type: HandType,
+    strength: usize,
+    substitute: Option<char>,
+    wild: Option<char>,
+}
+
+impl Hand {
+    fn parse(value: &str, rules: &Rules) -> Self {
+        assert_eq!(
+            value.chars().count(),
+            rules.hand_size,
+            "Hand {value} must have {} cards",
+            rules.hand_size
+        );
+        let labels = value.to_string();
+
+        let cards = labels.chars().map(|card| rules.strength(card)).collect();
+        let substitute = rules.substitute(&labels);
+        let hand_type = rules.classify(&labels, substitute);
+        let strength = rules.type_strength(&hand_type);
+
+        Self {
+            labels,
+            cards,
+            hand_type,
+            strength,
+            substitute,
+            wild: rules.wild,
         }
     }
 }
 
 impl Display for Hand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Hand::HighCard(cards) => {
-                write!(f, "HighCard ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
-            Hand::OnePair(cards) => {
-                write!(f, "OnePair ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
-            Hand::TwoPair(cards) => {
-                write!(f, "TwoPair ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
-            Hand::ThreeOfAKind(cards) => {
-                write!(f, "ThreeOfAKind ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
-            Hand::FourOfAKind(cards) => {
-                write!(f, "FourOfAKind ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
-            Hand::FiveOfAKind(cards) => {
-                write!(f, "FiveOfAKind ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
-            Hand::FullHouse(cards) => {
-                write!(f, "FullHouse ")?;
-                cards.iter().try_for_each(|card| write!(f, "{}", card))
-            }
+        write!(f, "{} {}", self.hand_type, self.labels)?;
+        if let Some(substitute) = self.substitute {
+            write!(f, " (wild as {substitute})")?;
         }
+        Ok(())
     }
 }
 
 impl PartialEq for Hand {
     fn eq(&self, other: &Self) -> bool {
-        self.rank().eq(&other.rank())
+        self.cmp(other) == Ordering::Equal
     }
 }
 
+impl Eq for Hand {}
+
 impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self == other {
-            let cards = self.cards();
-            let other_cards = other.cards();
-
-            for i in 0..cards.len() {
-                if cards[i] == other_cards[i] {
-                    continue;
-                } else {
-                    return cards[i].partial_cmp(&other_cards[i]);
-                }
-            }
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Hands parsed with different rules may share type and card strengths, so the wild card and
+// labels break the remaining ties to keep the order total and consistent with `Eq`.
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.strength
+            .cmp(&other.strength)
+            .then_with(|| self.cards.cmp(&other.cards))
+            .then_with(|| self.wild.cmp(&other.wild))
+            .then_with(|| self.labels.cmp(&other.labels))
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tiebreak {
+    HandType,
+    Card(usize),
+    Identical,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct Explanation {
+    left: Hand,
+    right: Hand,
+    ordering: Ordering,
+    tiebreak: Tiebreak,
+}
+
+#[cfg(test)]
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.left)?;
+        writeln!(f, "{}", self.right)?;
 
-            Some(std::cmp::Ordering::Equal)
-        } else {
-            self.rank().partial_cmp(&other.rank())
+        let ordering = match self.ordering {
+            Ordering::Less => "<",
+            Ordering::Equal => "=",
+            Ordering::Greater => ">",
+        };
+        write!(f, "{} {ordering} {}: ", self.left.labels, self.right.labels)?;
+
+        match self.tiebreak {
+            Tiebreak::HandType => write!(
+                f,
+                "{} {ordering} {}",
+                self.left.hand_type, self.right.hand_type
+            ),
+            Tiebreak::Card(position) => write!(
+                f,
+                "same type, card {} {ordering} {} at position {}",
+                self.left.labels.chars().nth(position).unwrap(),
+                self.right.labels.chars().nth(position).unwrap(),
+                position + 1
+            ),
+            Tiebreak::Identical => write!(f, "same type and cards"),
         }
     }
 }
 
-fn part01(input: &str) -> u64 {
+#[cfg(test)]
+fn explain(left: &Hand, right: &Hand) -> Explanation {
+    let tiebreak = if left.strength != right.strength {
+        Tiebreak::HandType
+    } else {
+        left.cards
+            .iter()
+            .zip(&right.cards)
+            .position(|(a, b)| a != b)
+            .map_or(Tiebreak::Identical, Tiebreak::Card)
+    };
+
+    Explanation {
+        left: left.clone(),
+        right: right.clone(),
+        ordering: left.cmp(right),
+        tiebreak,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RankedHand {
+    rank: u64,
+    hand: Hand,
+    bid: u64,
+}
+
+impl RankedHand {
+    fn winnings(&self) -> u64 {
+        self.rank * self.bid
+    }
+}
+
+impl Display for RankedHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} bid {} wins {}",
+            self.rank,
+            self.hand,
+            self.bid,
+            self.winnings()
+        )
+    }
+}
+
+fn ranked_hands(input: &str, rules: &Rules) -> Vec<RankedHand> {
     let mut hands = input
         .lines()
         .map(|line| {
             let (cards, bid) = line.split_once(' ').unwrap();
-            let hand: Hand = Hand::parse(cards, Joker::Ignore);
+            let hand = Hand::parse(cards, rules);
             let bid = bid.parse::<u64>().unwrap();
 
             (hand, bid)
@@ -319,33 +334,92 @@ fn part01(input: &str) -> u64 {
     hands
         .into_iter()
         .enumerate()
-        .fold(0, |mut sum, (rank, (_, bid))| {
-            sum += (rank + 1) as u64 * bid;
-            sum
+        .map(|(rank, (hand, bid))| RankedHand {
+            rank: rank as u64 + 1,
+            hand,
+            bid,
         })
+        .collect()
 }
 
-fn part02(input: &str) -> u64 {
-    let mut hands = input
-        .lines()
-        .map(|line| {
-            let (cards, bid) = line.split_once(' ').unwrap();
-            let hand: Hand = Hand::parse(cards, Joker::Use);
-            let bid = bid.parse::<u64>().unwrap();
+fn total_winnings(input: &str, rules: &Rules) -> u64 {
+    ranked_hands(input, rules)
+        .iter()
+        .map(|ranked| ranked.winnings())
+        .sum()
+}
 
-            (hand, bid)
-        })
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TypeDistribution {
+    total: u64,
+    counts: Vec<(HandType, u64)>,
+}
+
+#[cfg(test)]
+impl TypeDistribution {
+    fn probability(&self, hand_type: &HandType) -> f64 {
+        self.counts
+            .iter()
+            .find(|(t, _)| t == hand_type)
+            .map_or(0.0, |&(_, count)| count as f64 / self.total as f64)
+    }
+}
+
+#[cfg(test)]
+impl Display for TypeDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (hand_type, count) in &self.counts {
+            writeln!(
+                f,
+                "{hand_type}: {count}/{} ({:.4}%)",
+                self.total,
+                self.probability(hand_type) * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Every '?' in `partial` is replaced by each card in the deck, as if drawn with replacement.
+#[cfg(test)]
+fn type_distribution(partial: &str, rules: &Rules) -> TypeDistribution {
+    let unknowns = partial.chars().filter(|&c| c == '?').count() as u32;
+    let deck = rules.ranks.len() as u64;
+    let total = deck.pow(unknowns);
+
+    let mut counts = rules
+        .precedence
+        .iter()
+        .map(|hand_type| (hand_type.clone(), 0))
         .collect::<Vec<_>>();
 
-    hands.sort_by(|(hand_a, _), (hand_b, _)| hand_a.cmp(hand_b));
+    for mut index in 0..total {
+        let cards = partial
+            .chars()
+            .map(|card| {
+                if card == '?' {
+                    let card = rules.ranks[(index % deck) as usize];
+                    index /= deck;
+                    card
+                } else {
+                    card
+                }
+            })
+            .collect::<String>();
 
-    hands
-        .into_iter()
-        .enumerate()
-        .fold(0, |mut sum, (rank, (_, bid))| {
-            sum += (rank + 1) as u64 * bid;
-            sum
-        })
+        counts[Hand::parse(&cards, rules).strength].1 += 1;
+    }
+
+    TypeDistribution { total, counts }
+}
+
+fn part01(input: &str) -> u64 {
+    total_winnings(input, &Rules::standard())
+}
+
+fn part02(input: &str) -> u64 {
+    total_winnings(input, &Rules::jokers())
 }
 
 fn main() {
@@ -356,6 +430,9 @@ fn main() {
 
 #[cfg(test)]
 mod test {
+    use std::cmp::Ordering;
+
+    use advent_of_code::xorshift::XorShift;
 
     #[test]
     fn part01() {
@@ -379,4 +456,215 @@ QQQJA 483
 ";
         assert_eq!(super::part02(input), 5905);
     }
+
+    #[test]
+    fn rules() {
+        use super::{Hand, HandType, Rules, WildUpgrade};
+
+        assert_eq!(
+            HandType::all(5)
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "HighCard",
+                "OnePair",
+                "TwoPair",
+                "ThreeOfAKind",
+                "FullHouse",
+                "FourOfAKind",
+                "FiveOfAKind"
+            ]
+        );
+
+        let rules = Rules::jokers();
+        assert_eq!(
+            Hand::parse("KTJJT", &rules).to_string(),
+            "FourOfAKind KTJJT (wild as T)"
+        );
+        assert_eq!(
+            Hand::parse("JJJJJ", &rules).to_string(),
+            "FiveOfAKind JJJJJ"
+        );
+
+        // Three cards per hand, where a pair beats three of a kind.
+        let rules = Rules {
+            ranks: "123".chars().collect(),
+            hand_size: 3,
+            wild: Some('1'),
+            upgrade: WildUpgrade::BestType,
+            precedence: vec![
+                HandType(vec![1, 1, 1]),
+                HandType(vec![3]),
+                HandType(vec![2, 1]),
+            ],
+        };
+        assert_eq!(
+            Hand::parse("123", &rules).to_string(),
+            "2-1 123 (wild as 3)"
+        );
+        assert_eq!(Hand::parse("322", &rules).to_string(), "2-1 322");
+        assert_eq!(Hand::parse("111", &rules).to_string(), "3 111 (wild as 3)");
+        assert!(Hand::parse("333", &rules) < Hand::parse("223", &rules));
+    }
+
+    #[test]
+    fn explain() {
+        use super::{Hand, Rules};
+
+        let rules = Rules::jokers();
+        let explanation =
+            super::explain(&Hand::parse("KTJJT", &rules), &Hand::parse("QQQJA", &rules));
+        assert_eq!(
+            explanation.to_string(),
+            "FourOfAKind KTJJT (wild as T)
+FourOfAKind QQQJA (wild as Q)
+KTJJT > QQQJA: same type, card K > Q at position 1"
+        );
+
+        let rules = Rules::standard();
+        let explanation =
+            super::explain(&Hand::parse("KK677", &rules), &Hand::parse("T55J5", &rules));
+        assert_eq!(
+            explanation.to_string(),
+            "TwoPair KK677
+ThreeOfAKind T55J5
+KK677 < T55J5: TwoPair < ThreeOfAKind"
+        );
+
+        let explanation =
+            super::explain(&Hand::parse("KK677", &rules), &Hand::parse("KK677", &rules));
+        assert!(explanation
+            .to_string()
+            .ends_with("KK677 = KK677: same type and cards"));
+    }
+
+    #[test]
+    fn ranked_hands() {
+        let input = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+";
+
+        let ranked = super::ranked_hands(input, &super::Rules::jokers())
+            .iter()
+            .map(|ranked| ranked.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            ranked,
+            vec![
+                "1: OnePair 32T3K bid 765 wins 765",
+                "2: TwoPair KK677 bid 28 wins 56",
+                "3: FourOfAKind T55J5 (wild as 5) bid 684 wins 2052",
+                "4: FourOfAKind QQQJA (wild as Q) bid 483 wins 1932",
+                "5: FourOfAKind KTJJT (wild as T) bid 220 wins 1100",
+            ]
+        );
+    }
+
+    fn random_hands(count: usize) -> Vec<super::Hand> {
+        let mut rng = XorShift::new(0x2545_f491_4f6c_dd1d);
+
+        let deck = "23456789TJQKA".chars().collect::<Vec<_>>();
+        let presets = [super::Rules::standard(), super::Rules::jokers()];
+
+        (0..count)
+            .map(|_| {
+                // Only a few distinct cards per hand, so equal types and ties show up often.
+                let pool = 2 + rng.below(4);
+                let cards = (0..5)
+                    .map(|_| deck[rng.below(pool) * 3 % deck.len()])
+                    .collect::<String>();
+                super::Hand::parse(&cards, &presets[rng.below(presets.len())])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hand_order_is_total() {
+        let hands = random_hands(60);
+
+        for a in &hands {
+            assert_eq!(a.cmp(a), Ordering::Equal);
+            assert_eq!(a, a);
+
+            for b in &hands {
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a} / {b}");
+                assert_eq!(a == b, a.cmp(b) == Ordering::Equal, "{a} / {b}");
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+
+                for c in &hands {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{a} / {b} / {c}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hand_equality() {
+        use super::{Hand, Rules};
+
+        let rules = Rules::standard();
+        assert_ne!(Hand::parse("AAKK2", &rules), Hand::parse("AAKK3", &rules));
+        assert_eq!(Hand::parse("AAKK2", &rules), Hand::parse("AAKK2", &rules));
+        assert_ne!(
+            Hand::parse("KTJJT", &rules),
+            Hand::parse("KTJJT", &Rules::jokers())
+        );
+        assert_ne!(
+            Hand::parse("22222", &rules),
+            Hand::parse("JJJJJ", &Rules::jokers())
+        );
+    }
+
+    #[test]
+    fn type_distribution() {
+        use super::{HandType, Rules, WildUpgrade};
+
+        let standard = super::type_distribution("?????", &Rules::standard());
+        assert_eq!(
+            standard.to_string(),
+            "HighCard: 154440/371293 (41.5952%)
+OnePair: 171600/371293 (46.2169%)
+TwoPair: 25740/371293 (6.9325%)
+ThreeOfAKind: 17160/371293 (4.6217%)
+FullHouse: 1560/371293 (0.4202%)
+FourOfAKind: 780/371293 (0.2101%)
+FiveOfAKind: 13/371293 (0.0035%)
+"
+        );
+
+        let jokers = super::type_distribution("?????", &Rules::jokers());
+        assert_eq!(jokers.counts.iter().map(|(_, c)| c).sum::<u64>(), 371293);
+        assert_eq!(jokers.probability(&HandType(vec![5])), 373.0 / 371293.0);
+
+        // Joining the largest group must be as good as trying every substitution.
+        let best = Rules {
+            upgrade: WildUpgrade::BestType,
+            ..Rules::jokers()
+        };
+        assert_eq!(
+            super::type_distribution("J????", &Rules::jokers()),
+            super::type_distribution("J????", &best)
+        );
+
+        let partial = super::type_distribution("KKJ?2", &Rules::jokers());
+        assert_eq!(partial.total, 13);
+        assert_eq!(partial.probability(&HandType(vec![4, 1])), 2.0 / 13.0);
+        assert_eq!(partial.probability(&HandType(vec![3, 2])), 1.0 / 13.0);
+        assert_eq!(partial.probability(&HandType(vec![3, 1, 1])), 10.0 / 13.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hand AAAAAK must have 5 cards")]
+    fn hand_too_long() {
+        use super::{Hand, Rules};
+
+        Hand::parse("AAAAAK", &Rules::standard());
+    }
 }
@@ -1,8 +1,5 @@
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy)]
-struct Node<'a>(&'a str, &'a str);
-
 fn lcm(a: u128, b: u128) -> u128 {
     a * b / gcd(a, b)
 }
@@ -11,9 +8,7 @@ fn gcd(a: u128, b: u128) -> u128 {
     let mut max = a;
     let mut min = b;
     if min > max {
-        let val = max;
-        max = min;
-        min = val;
+        std::mem::swap(&mut max, &mut min);
     }
 
     loop {
@@ -27,96 +22,397 @@ fn gcd(a: u128, b: u128) -> u128 {
     }
 }
 
-fn steps_count(instructions: Vec<char>, nodes: HashMap<&str, Node>, first_node: &str) -> u128 {
-    let mut steps = 0;
-    let mut next_instruction = 0;
-    let mut next_node = first_node;
+// Combines x ≡ a.0 (mod a.1) and x ≡ b.0 (mod b.1), when the moduli share factors too.
+fn crt(a: (u128, u128), b: (u128, u128)) -> Option<(u128, u128)> {
+    let (r1, m1) = a;
+    let (r2, m2) = b;
+    let g = gcd(m1, m2);
 
-    loop {
-        steps = steps + 1;
+    let diff = (r2 as i128 - r1 as i128).rem_euclid(m2 as i128) as u128;
+    if !diff.is_multiple_of(g) {
+        return None;
+    }
 
-        let instruction = instructions[next_instruction];
-        next_instruction += 1;
-        next_instruction %= instructions.len();
+    // m1 * k ≡ diff (mod m2), solved with the inverse of m1 / g modulo m2 / g.
+    let m = m2 / g;
+    let k = if m == 1 {
+        0
+    } else {
+        (diff / g % m) * mod_inverse((m1 / g) % m, m) % m
+    };
 
-        let node = nodes.get(next_node).unwrap();
-        next_node = match instruction {
-            'L' => node.0,
-            'R' => node.1,
-            _ => unreachable!(),
-        };
+    let modulus = lcm(m1, m2);
+    Some(((r1 + m1 * k) % modulus, modulus))
+}
+
+fn mod_inverse(a: u128, m: u128) -> u128 {
+    let (mut old_r, mut r) = (a as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
 
-        if next_node.ends_with("Z") {
-            return steps;
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+
+    old_s.rem_euclid(m as i128) as u128
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GhostCycle {
+    tail: u128,
+    length: u128,
+    tail_hits: Vec<u128>,
+    cycle_hits: Vec<u128>,
+    path: Vec<usize>,
+}
+
+impl GhostCycle {
+    fn is_hit(&self, step: u128) -> bool {
+        if step == 0 {
+            false
+        } else if step < self.tail {
+            self.tail_hits.binary_search(&step).is_ok()
+        } else {
+            let offset = self.tail + (step - self.tail) % self.length;
+            self.cycle_hits.binary_search(&offset).is_ok()
         }
     }
+
+    fn cycle_edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let cycle = &self.path[self.tail as usize..];
+        cycle
+            .iter()
+            .copied()
+            .zip(cycle.iter().copied().cycle().skip(1))
+    }
 }
 
-fn part01(input: &str) -> u128 {
-    let instructions = input
-        .split("\n\n")
-        .next()
-        .unwrap()
-        .chars()
-        .collect::<Vec<_>>();
-
-    let nodes = input
-        .lines()
-        .skip(2)
-        .map(|line| {
-            let (key, lr) = line.split_once('=').unwrap();
-            let key = key.trim();
-            let (left, right) = lr
-                .trim()
-                .trim_matches(|c| c == '(' || c == ')')
-                .split_once(',')
-                .unwrap();
-
-            (key, Node(left.trim(), right.trim()))
-        })
-        .collect::<HashMap<_, _>>();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Left,
+    Right,
+}
 
-    steps_count(instructions, nodes, "AAA")
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NetworkError {
+    InvalidInstruction(char),
+    InvalidNode(String),
+    UnknownNode(String),
 }
 
-fn part02(input: &str) -> u128 {
-    let instructions = input
-        .split("\n\n")
-        .next()
-        .unwrap()
-        .chars()
-        .collect::<Vec<_>>();
-
-    let nodes = input
-        .lines()
-        .skip(2)
-        .map(|line| {
-            let (key, lr) = line.split_once('=').unwrap();
-            let key = key.trim();
-            let (left, right) = lr
-                .trim()
-                .trim_matches(|c| c == '(' || c == ')')
-                .split_once(',')
-                .unwrap();
-
-            (key, Node(left.trim(), right.trim()))
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::InvalidInstruction(c) => write!(f, "Invalid instruction: {c}"),
+            NetworkError::InvalidNode(line) => write!(f, "Invalid node: {line}"),
+            NetworkError::UnknownNode(name) => write!(f, "Unknown node: {name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodePattern {
+    Exact(String),
+    Suffix(String),
+    #[allow(dead_code)]
+    Glob(String),
+}
+
+impl NodePattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NodePattern::Exact(exact) => name == exact,
+            NodePattern::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            NodePattern::Glob(glob) => Self::glob_matches(glob.as_bytes(), name.as_bytes()),
+        }
+    }
+
+    // '?' matches any single character and '*' matches any sequence, including an empty one.
+    fn glob_matches(glob: &[u8], name: &[u8]) -> bool {
+        match (glob.first(), name.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => {
+                Self::glob_matches(&glob[1..], name)
+                    || (!name.is_empty() && Self::glob_matches(glob, &name[1..]))
+            }
+            (Some(&g), Some(&n)) if g == b'?' || g == n => {
+                Self::glob_matches(&glob[1..], &name[1..])
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Network<'a> {
+    instructions: Vec<Direction>,
+    names: Vec<&'a str>,
+    ids: HashMap<&'a str, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl<'a> Network<'a> {
+    fn parse(input: &'a str) -> Result<Self, NetworkError> {
+        let instructions = input
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| match c {
+                'L' => Ok(Direction::Left),
+                'R' => Ok(Direction::Right),
+                _ => Err(NetworkError::InvalidInstruction(c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let nodes = input
+            .lines()
+            .skip(2)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (key, lr) = line
+                    .split_once('=')
+                    .ok_or_else(|| NetworkError::InvalidNode(line.to_string()))?;
+                let (left, right) = lr
+                    .trim()
+                    .trim_matches(|c| c == '(' || c == ')')
+                    .split_once(',')
+                    .ok_or_else(|| NetworkError::InvalidNode(line.to_string()))?;
+
+                Ok((key.trim(), left.trim(), right.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let names = nodes.iter().map(|&(name, _, _)| name).collect::<Vec<_>>();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, &name)| (name, id))
+            .collect::<HashMap<_, _>>();
+
+        let id = |name: &str| {
+            ids.get(name)
+                .copied()
+                .ok_or_else(|| NetworkError::UnknownNode(name.to_string()))
+        };
+
+        let left = nodes
+            .iter()
+            .map(|&(_, left, _)| id(left))
+            .collect::<Result<Vec<_>, _>>()?;
+        let right = nodes
+            .iter()
+            .map(|&(_, _, right)| id(right))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            instructions,
+            names,
+            ids,
+            left,
+            right,
         })
-        .collect::<HashMap<_, _>>();
+    }
+
+    fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    fn step(&self, node: usize, instruction: usize) -> usize {
+        match self.instructions[instruction] {
+            Direction::Left => self.left[node],
+            Direction::Right => self.right[node],
+        }
+    }
+
+    fn matching(&self, pattern: &NodePattern) -> Vec<usize> {
+        match pattern {
+            NodePattern::Exact(name) => self.id(name).into_iter().collect(),
+            _ => (0..self.names.len())
+                .filter(|&id| pattern.matches(self.names[id]))
+                .collect(),
+        }
+    }
+
+    fn goals(&self, goal: &NodePattern) -> Vec<bool> {
+        self.names.iter().map(|name| goal.matches(name)).collect()
+    }
+
+    fn ghost_cycle(&self, start: usize, goal: &NodePattern) -> GhostCycle {
+        let goals = self.goals(goal);
+        let mut seen = HashMap::new();
+        let mut hits = vec![];
+        let mut path = vec![];
+        let mut node = start;
+        let mut step = 0u128;
+
+        loop {
+            let instruction = (step % self.instructions.len() as u128) as usize;
+            if let Some(&first_seen) = seen.get(&(node, instruction)) {
+                let (tail_hits, cycle_hits) = hits.into_iter().partition(|&hit| hit < first_seen);
+
+                return GhostCycle {
+                    tail: first_seen,
+                    length: step - first_seen,
+                    tail_hits,
+                    cycle_hits,
+                    path,
+                };
+            }
+            seen.insert((node, instruction), step);
+
+            if goals[node] {
+                hits.push(step);
+            }
+
+            path.push(node);
+            node = self.step(node, instruction);
+            step += 1;
+        }
+    }
+
+    // Walks every ghost in lock-step, which is only practical on small networks, but it doesn't
+    // rely on any cycle analysis so it can be used to validate it.
+    #[cfg(test)]
+    fn simulate(&self, starts: &[usize], goal: &NodePattern, limit: u128) -> Option<u128> {
+        let goals = self.goals(goal);
+        let mut nodes = starts.to_vec();
+
+        for step in 1..=limit {
+            let instruction = ((step - 1) % self.instructions.len() as u128) as usize;
+            nodes
+                .iter_mut()
+                .for_each(|node| *node = self.step(*node, instruction));
+
+            if nodes.iter().all(|&node| goals[node]) {
+                return Some(step);
+            }
+        }
+
+        None
+    }
+
+    fn first_simultaneous_step(&self, start: &NodePattern, goal: &NodePattern) -> Option<u128> {
+        let cycles = self
+            .matching(start)
+            .into_iter()
+            .map(|start| self.ghost_cycle(start, goal))
+            .collect::<Vec<_>>();
+
+        first_simultaneous_step(&cycles)
+    }
+
+    fn to_dot(&self, start: &NodePattern, goal: &NodePattern) -> String {
+        const COLORS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
+
+        let mut highlighted = HashMap::new();
+        for (index, start) in self.matching(start).into_iter().enumerate() {
+            for edge in self.ghost_cycle(start, goal).cycle_edges() {
+                highlighted.insert(edge, COLORS[index % COLORS.len()]);
+            }
+        }
+
+        let mut dot = String::from("digraph network {\n");
+        for name in &self.names {
+            if start.matches(name) {
+                dot += &format!("    \"{name}\" [style=filled, fillcolor=lightblue];\n");
+            } else if goal.matches(name) {
+                dot += &format!("    \"{name}\" [style=filled, fillcolor=lightcoral];\n");
+            }
+        }
+
+        for (id, name) in self.names.iter().enumerate() {
+            let (left, right) = (self.left[id], self.right[id]);
+            let edges = if left == right {
+                vec![(left, "LR")]
+            } else {
+                vec![(left, "L"), (right, "R")]
+            };
+
+            for (target, label) in edges {
+                let target_name = self.names[target];
+                match highlighted.get(&(id, target)) {
+                    Some(color) => {
+                        dot += &format!(
+                            "    \"{name}\" -> \"{target_name}\" [label=\"{label}\", color={color}, penwidth=2];\n"
+                        )
+                    }
+                    None => {
+                        dot += &format!("    \"{name}\" -> \"{target_name}\" [label=\"{label}\"];\n")
+                    }
+                }
+            }
+        }
+
+        dot += "}\n";
+        dot
+    }
+}
 
-    let count = nodes
-        .keys()
-        .copied()
-        .filter(|k| k.ends_with("A"))
-        .map(|node| steps_count(instructions.clone(), nodes.clone(), node))
-        .collect::<Vec<_>>();
+fn first_simultaneous_step(cycles: &[GhostCycle]) -> Option<u128> {
+    let bound = cycles.iter().map(|cycle| cycle.tail).max()?.max(1);
 
-    count
+    if let Some(step) = (1..bound).find(|&step| cycles.iter().all(|c| c.is_hit(step))) {
+        return Some(step);
+    }
+
+    // Past every tail, each ghost is on a Z node exactly at the steps congruent to one of its
+    // cycle hits, so every choice of hits gives a system of congruences.
+    let mut solutions = vec![(0, 1)];
+    for cycle in cycles {
+        solutions = solutions
+            .into_iter()
+            .flat_map(|solution| {
+                cycle
+                    .cycle_hits
+                    .iter()
+                    .filter_map(move |&hit| crt(solution, (hit % cycle.length, cycle.length)))
+            })
+            .collect();
+    }
+
+    solutions
         .into_iter()
-        .fold(1, |product, count| lcm(product, count))
+        .map(|(residue, modulus)| {
+            if residue >= bound {
+                residue
+            } else {
+                residue + (bound - residue).div_ceil(modulus) * modulus
+            }
+        })
+        .min()
+}
+
+fn part01(input: &str) -> u128 {
+    let start = NodePattern::Exact("AAA".to_string());
+    let goal = NodePattern::Exact("ZZZ".to_string());
+
+    Network::parse(input)
+        .unwrap()
+        .first_simultaneous_step(&start, &goal)
+        .unwrap()
+}
+
+fn part02(input: &str) -> u128 {
+    let start = NodePattern::Suffix("A".to_string());
+    let goal = NodePattern::Suffix("Z".to_string());
+
+    Network::parse(input)
+        .unwrap()
+        .first_simultaneous_step(&start, &goal)
+        .unwrap()
 }
 
 fn main() {
     let input = include_str!("../input/day08.input");
+    if std::env::args().any(|arg| arg == "--dot") {
+        let start = NodePattern::Suffix("A".to_string());
+        let goal = NodePattern::Suffix("Z".to_string());
+        print!("{}", Network::parse(input).unwrap().to_dot(&start, &goal));
+        return;
+    }
     println!("Part 01: {}", part01(input));
     println!("Part 02: {}", part02(input));
 }
@@ -168,4 +464,171 @@ XXX = (XXX, XXX)
 
         assert_eq!(super::part02(input), 6);
     }
+
+    #[test]
+    fn offset_cycles() {
+        let input = "L
+
+11A = (11B, XXX)
+11B = (11Z, XXX)
+11Z = (11C, XXX)
+11C = (11B, XXX)
+22A = (22Z, XXX)
+22Z = (22A, XXX)
+XXX = (XXX, XXX)
+";
+
+        let network = super::Network::parse(input).unwrap();
+        let goal = super::NodePattern::Suffix("Z".to_string());
+        let cycle = network.ghost_cycle(network.id("11A").unwrap(), &goal);
+        assert_eq!(cycle.tail, 1);
+        assert_eq!(cycle.length, 3);
+        assert_eq!(cycle.tail_hits, vec![]);
+        assert_eq!(cycle.cycle_hits, vec![2]);
+        assert_eq!(
+            cycle
+                .path
+                .iter()
+                .map(|&id| network.names[id])
+                .collect::<Vec<_>>(),
+            vec!["11A", "11B", "11Z", "11C"]
+        );
+
+        // Plain LCM of the first hits (2 and 1) would answer 2.
+        assert_eq!(super::part02(input), 5);
+    }
+
+    #[test]
+    fn no_simultaneous_step() {
+        let input = "L
+
+22A = (22Z, XXX)
+22Z = (22A, XXX)
+33A = (33B, XXX)
+33B = (33Z, XXX)
+33Z = (33B, XXX)
+XXX = (XXX, XXX)
+";
+
+        let network = super::Network::parse(input).unwrap();
+        let goal = super::NodePattern::Suffix("Z".to_string());
+        let cycles =
+            ["22A", "33A"].map(|start| network.ghost_cycle(network.id(start).unwrap(), &goal));
+
+        assert_eq!(super::first_simultaneous_step(&cycles), None);
+    }
+
+    #[test]
+    fn crt() {
+        assert_eq!(super::crt((2, 3), (1, 2)), Some((5, 6)));
+        assert_eq!(super::crt((2, 4), (4, 6)), Some((10, 12)));
+        assert_eq!(super::crt((1, 4), (2, 6)), None);
+    }
+
+    #[test]
+    fn network_errors() {
+        use super::{Network, NetworkError};
+
+        assert_eq!(
+            Network::parse("LXR\n\nAAA = (AAA, AAA)").unwrap_err(),
+            NetworkError::InvalidInstruction('X')
+        );
+        assert_eq!(
+            Network::parse("LR\n\nAAA = (BBB, AAA)").unwrap_err(),
+            NetworkError::UnknownNode("BBB".to_string())
+        );
+        assert_eq!(
+            Network::parse("LR\n\nAAA (AAA, AAA)").unwrap_err(),
+            NetworkError::InvalidNode("AAA (AAA, AAA)".to_string())
+        );
+    }
+
+    #[test]
+    fn to_dot() {
+        let input = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+XXX = (XXX, XXX)
+";
+
+        let network = super::Network::parse(input).unwrap();
+        let start = super::NodePattern::Suffix("A".to_string());
+        let goal = super::NodePattern::Suffix("Z".to_string());
+        assert_eq!(network.matching(&start), vec![0]);
+        assert_eq!(
+            network.matching(&super::NodePattern::Exact("11Z".to_string())),
+            vec![2]
+        );
+        assert!(network
+            .matching(&super::NodePattern::Exact("AAA".to_string()))
+            .is_empty());
+        assert_eq!(
+            network.to_dot(&start, &goal),
+            "digraph network {
+    \"11A\" [style=filled, fillcolor=lightblue];
+    \"11Z\" [style=filled, fillcolor=lightcoral];
+    \"11A\" -> \"11B\" [label=\"L\"];
+    \"11A\" -> \"XXX\" [label=\"R\"];
+    \"11B\" -> \"XXX\" [label=\"L\"];
+    \"11B\" -> \"11Z\" [label=\"R\", color=red, penwidth=2];
+    \"11Z\" -> \"11B\" [label=\"L\", color=red, penwidth=2];
+    \"11Z\" -> \"XXX\" [label=\"R\"];
+    \"XXX\" -> \"XXX\" [label=\"LR\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn node_patterns() {
+        use super::NodePattern;
+
+        assert!(NodePattern::Exact("AAA".to_string()).matches("AAA"));
+        assert!(!NodePattern::Exact("AAA".to_string()).matches("BAA"));
+        assert!(NodePattern::Suffix("Z".to_string()).matches("11Z"));
+        assert!(NodePattern::Glob("1?Z".to_string()).matches("11Z"));
+        assert!(!NodePattern::Glob("1?Z".to_string()).matches("22Z"));
+        assert!(NodePattern::Glob("*Z".to_string()).matches("ZZZ"));
+        assert!(NodePattern::Glob("A*B*".to_string()).matches("AXBY"));
+        assert!(!NodePattern::Glob("A*B".to_string()).matches("AXBY"));
+    }
+
+    #[test]
+    fn simulate_matches_cycles() {
+        use super::{Network, NodePattern};
+        use advent_of_code::xorshift::XorShift;
+
+        let names = ["AAA", "BBA", "CCB", "DDZ", "EEZ", "FFC", "GGA", "HHZ"];
+        let start = NodePattern::Suffix("A".to_string());
+        let goal = NodePattern::Suffix("Z".to_string());
+
+        let mut rng = XorShift::new(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..200 {
+            let instructions = (0..1 + rng.below(4))
+                .map(|_| if rng.below(2) == 0 { 'L' } else { 'R' })
+                .collect::<String>();
+            let nodes = names
+                .iter()
+                .map(|name| {
+                    let left = names[rng.below(names.len())];
+                    let right = names[rng.below(names.len())];
+                    format!("{name} = ({left}, {right})")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let input = format!("{instructions}\n\n{nodes}");
+
+            let network = Network::parse(&input).unwrap();
+            let starts = network.matching(&start);
+
+            assert_eq!(
+                network.simulate(&starts, &goal, 10_000),
+                network.first_simultaneous_step(&start, &goal),
+                "{input}"
+            );
+        }
+    }
 }
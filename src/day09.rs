@@ -1,79 +1,359 @@
-fn predict(line: &str) -> i64 {
-    let values = line
-        .split_whitespace()
-        .map(|c| c.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
-
-    let mut interpolation = values.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
-    let mut interpolations = vec![interpolation];
-
-    loop {
-        interpolation = interpolations
-            .last()
-            .unwrap()
-            .windows(2)
-            .map(|w| w[1] - w[0])
-            .collect::<Vec<_>>();
-
-        if interpolation.iter().all(|&v| v == 0) {
-            break;
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SequenceError {
+    InvalidValue(String),
+    Empty,
+    NotPolynomial,
+}
+
+impl std::fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceError::InvalidValue(value) => write!(f, "invalid value: {value}"),
+            SequenceError::Empty => write!(f, "empty sequence"),
+            SequenceError::NotPolynomial => {
+                write!(f, "difference table never reaches an all-zero row")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sequence {
+    values: Vec<i128>,
+    // First entry of every row of the difference table, which are the coefficients of the
+    // Newton forward-difference polynomial.
+    leading: Vec<i128>,
+}
+
+impl Sequence {
+    fn parse(line: &str) -> Result<Self, SequenceError> {
+        let values = line
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse::<i128>()
+                    .map_err(|_| SequenceError::InvalidValue(value.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::new(values)
+    }
+
+    fn new(values: Vec<i128>) -> Result<Self, SequenceError> {
+        if values.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+
+        let mut leading = vec![];
+        let mut row = values.clone();
+
+        while row.iter().any(|&value| value != 0) {
+            if row.len() == 1 {
+                return Err(SequenceError::NotPolynomial);
+            }
+
+            leading.push(row[0]);
+            row = row.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+
+        Ok(Self { values, leading })
+    }
+
+    // `None` for the all-zero sequence.
+    fn degree(&self) -> Option<usize> {
+        self.leading.len().checked_sub(1)
+    }
+
+    // f(n) = sum of C(n, k) * leading[k], where C(n, k) is extended to negative n so the
+    // sequence can be extrapolated backwards as well.
+    fn evaluate(&self, index: i128) -> i128 {
+        let mut binomial = 1;
+        let mut value = 0;
+
+        for (k, &coefficient) in self.leading.iter().enumerate() {
+            value += binomial * coefficient;
+            binomial = binomial * (index - k as i128) / (k as i128 + 1);
+        }
+
+        value
+    }
+
+    // Coefficients of the closed form in powers of n, starting from the constant term.
+    fn coefficients(&self) -> Vec<Rational> {
+        let Some(degree) = self.degree() else {
+            return vec![];
+        };
+
+        // Scaling by degree! keeps every intermediate value integral.
+        let scale = (1..=degree as i128).product::<i128>();
+        let mut numerators = vec![0; degree + 1];
+        let mut falling = vec![1];
+        let mut factorial = 1;
+
+        for (k, &coefficient) in self.leading.iter().enumerate() {
+            for (power, &value) in falling.iter().enumerate() {
+                numerators[power] += coefficient * value * (scale / factorial);
+            }
+
+            // n (n - 1) ... (n - k) from n (n - 1) ... (n - k + 1).
+            let mut next = vec![0; falling.len() + 1];
+            for (power, &value) in falling.iter().enumerate() {
+                next[power + 1] += value;
+                next[power] -= value * k as i128;
+            }
+            falling = next;
+            factorial *= k as i128 + 1;
+        }
+
+        numerators
+            .into_iter()
+            .map(|numerator| Rational::new(numerator, scale))
+            .collect()
+    }
+
+    fn ahead(&self, steps: i128) -> i128 {
+        self.evaluate(self.values.len() as i128 - 1 + steps)
+    }
+
+    fn behind(&self, steps: i128) -> i128 {
+        self.evaluate(-steps)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    fn new(numerator: i128, denominator: i128) -> Self {
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+}
+
+impl std::fmt::Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn difference_row(values: &[i128], order: usize) -> Vec<i128> {
+    let mut row = values.to_vec();
+    for _ in 0..order {
+        row = row.windows(2).map(|w| w[1] - w[0]).collect();
+    }
+    row
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Correction {
+    index: usize,
+    value: i128,
+    corrected: i128,
+    degree: Option<usize>,
+}
+
+// Every single value change that turns the sequence into a polynomial which is still checked by
+// at least one extra value, i.e. whose difference table of order len - 2 becomes all zero. Each
+// entry of that row is linear in the changed value, so the correction can be solved for directly.
+fn corrections(values: &[i128]) -> Vec<Correction> {
+    if values.len() < 3 {
+        return vec![];
+    }
+    let order = values.len() - 2;
+
+    let mut corrections = vec![];
+    for index in 0..values.len() {
+        let mut base = values.to_vec();
+        base[index] = 0;
+        let mut unit = vec![0; values.len()];
+        unit[index] = 1;
+
+        let mut corrected = None;
+        let mut consistent = true;
+        for (base, unit) in difference_row(&base, order)
+            .into_iter()
+            .zip(difference_row(&unit, order))
+        {
+            if unit == 0 {
+                consistent &= base == 0;
+            } else if base % unit != 0
+                || corrected.is_some_and(|corrected| corrected != -base / unit)
+            {
+                consistent = false;
+            } else {
+                corrected = Some(-base / unit);
+            }
         }
 
-        interpolations.push(interpolation);
+        let Some(corrected) =
+            corrected.filter(|&corrected| consistent && corrected != values[index])
+        else {
+            continue;
+        };
+
+        let mut fixed = values.to_vec();
+        fixed[index] = corrected;
+        if let Ok(sequence) = Sequence::new(fixed) {
+            corrections.push(Correction {
+                index,
+                value: values[index],
+                corrected,
+                degree: sequence.degree(),
+            });
+        }
     }
 
-    let diff = interpolations
-        .into_iter()
-        .rev()
-        .map(|interpolation| *interpolation.last().unwrap())
-        .sum::<i64>();
+    corrections
+}
 
-    diff + values.last().unwrap()
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineReport {
+    line: usize,
+    sequence: Result<Sequence, SequenceError>,
+    corrections: Vec<Correction>,
 }
 
-fn predict_back(line: &str) -> i64 {
-    let values = line
-        .split_whitespace()
-        .map(|c| c.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
+impl LineReport {
+    fn new(line: usize, text: &str) -> Self {
+        let sequence = Sequence::parse(text);
+        let corrections = match &sequence {
+            Err(SequenceError::NotPolynomial) => corrections(
+                &text
+                    .split_whitespace()
+                    .map(|value| value.parse().unwrap())
+                    .collect::<Vec<_>>(),
+            ),
+            _ => vec![],
+        };
 
-    let mut interpolation = values.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
-    let mut interpolations = vec![interpolation];
+        Self {
+            line,
+            sequence,
+            corrections,
+        }
+    }
+
+    fn is_anomaly(&self) -> bool {
+        self.sequence.is_err()
+    }
+}
 
-    loop {
-        interpolation = interpolations
-            .last()
-            .unwrap()
-            .windows(2)
-            .map(|w| w[0] - w[1])
-            .collect::<Vec<_>>();
+fn format_polynomial(coefficients: &[Rational]) -> String {
+    let mut polynomial = String::new();
 
-        if interpolation.iter().all(|&v| v == 0) {
-            break;
+    for (power, coefficient) in coefficients.iter().enumerate().rev() {
+        if coefficient.numerator == 0 {
+            continue;
         }
 
-        interpolations.push(interpolation);
+        let magnitude = Rational::new(coefficient.numerator.abs(), coefficient.denominator);
+        if polynomial.is_empty() {
+            polynomial += if coefficient.numerator < 0 { "-" } else { "" };
+        } else {
+            polynomial += if coefficient.numerator < 0 {
+                " - "
+            } else {
+                " + "
+            };
+        }
+
+        let variable = match power {
+            0 => String::new(),
+            1 => "n".to_string(),
+            _ => format!("n^{power}"),
+        };
+        if power == 0 || magnitude != Rational::new(1, 1) {
+            polynomial += &magnitude.to_string();
+        }
+        polynomial += &variable;
     }
 
-    let diff = interpolations
-        .into_iter()
-        .rev()
-        .map(|interpolation| *interpolation.first().unwrap())
-        .sum::<i64>();
+    if polynomial.is_empty() {
+        polynomial += "0";
+    }
+
+    polynomial
+}
+
+impl std::fmt::Display for LineReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
+
+        match &self.sequence {
+            Ok(sequence) => {
+                let degree = sequence
+                    .degree()
+                    .map_or("none".to_string(), |degree| degree.to_string());
+                write!(
+                    f,
+                    "degree {degree}, f(n) = {}",
+                    format_polynomial(&sequence.coefficients())
+                )
+            }
+            Err(error) => {
+                write!(f, "{error}")?;
+                for correction in &self.corrections {
+                    write!(
+                        f,
+                        "\n  value {} at index {} should probably be {}",
+                        correction.value, correction.index, correction.corrected
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn report(input: &str) -> Vec<LineReport> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(line, text)| LineReport::new(line + 1, text))
+        .collect()
+}
 
-    values.first().unwrap() - diff
+fn predict(line: &str) -> i128 {
+    Sequence::parse(line).unwrap().ahead(1)
 }
 
-fn part01(input: &str) -> i64 {
-    input.lines().map(|line| predict(line)).sum()
+fn predict_back(line: &str) -> i128 {
+    Sequence::parse(line).unwrap().behind(1)
 }
 
-fn part02(input: &str) -> i64 {
-    input.lines().map(|line| predict_back(line)).sum()
+fn part01(input: &str) -> i128 {
+    input.lines().map(predict).sum()
+}
+
+fn part02(input: &str) -> i128 {
+    input.lines().map(predict_back).sum()
 }
 
 fn main() {
     let input = include_str!("../input/day09.input");
+    for report in report(input) {
+        if report.is_anomaly() {
+            println!("{report}");
+        }
+    }
     println!("Part 01: {}", part01(input));
     println!("Part 02: {}", part02(input));
 }
@@ -128,4 +408,88 @@ mod test {
 ";
         assert_eq!(super::part02(input), 2);
     }
+
+    #[test]
+    fn evaluate() {
+        let sequence = super::Sequence::parse("1 3 6 10 15 21").unwrap();
+
+        assert_eq!(sequence.degree(), Some(2));
+        assert_eq!(sequence.evaluate(0), 1);
+        assert_eq!(sequence.evaluate(9), 55);
+        assert_eq!(sequence.ahead(4), 55);
+        assert_eq!(sequence.behind(1), 0);
+        assert_eq!(sequence.behind(3), 1);
+        assert_eq!(sequence.evaluate(-100), 4851);
+    }
+
+    #[test]
+    fn degree() {
+        let degree = |line| super::Sequence::parse(line).unwrap().degree();
+
+        assert_eq!(degree("0 0 0"), None);
+        assert_eq!(degree("7 7 7"), Some(0));
+        assert_eq!(degree("0 3 6 9 12 15"), Some(1));
+        assert_eq!(degree("-1 0 7 26 63"), Some(3));
+    }
+
+    #[test]
+    fn sequence_errors() {
+        use super::{Sequence, SequenceError};
+
+        assert_eq!(Sequence::parse(""), Err(SequenceError::Empty));
+        assert_eq!(
+            Sequence::parse("1 x 3"),
+            Err(SequenceError::InvalidValue("x".to_string()))
+        );
+        assert_eq!(
+            Sequence::parse("1 2 4 8 16 32"),
+            Err(SequenceError::NotPolynomial)
+        );
+        assert_eq!(Sequence::parse("1 4 9"), Err(SequenceError::NotPolynomial));
+        assert_eq!(Sequence::parse("5"), Err(SequenceError::NotPolynomial));
+    }
+
+    #[test]
+    fn coefficients() {
+        let format =
+            |line| super::format_polynomial(&super::Sequence::parse(line).unwrap().coefficients());
+
+        assert_eq!(format("0 0 0"), "0");
+        assert_eq!(format("7 7 7"), "7");
+        assert_eq!(format("0 3 6 9 12 15"), "3n");
+        assert_eq!(format("1 3 6 10 15 21"), "1/2n^2 + 3/2n + 1");
+        assert_eq!(format("10 13 16 21 30 45"), "1/3n^3 - n^2 + 11/3n + 10");
+        assert_eq!(format("-1 0 7 26 63"), "n^3 - 1");
+        assert_eq!(format("5 4 1 -4"), "-n^2 + 5");
+    }
+
+    #[test]
+    fn report() {
+        let input = "0 3 6 9 12 15
+1 3 6 11 15 21
+1 2 4 8 16 32
+1 x 3
+";
+        let reports = super::report(input);
+
+        assert!(!reports[0].is_anomaly());
+        assert_eq!(reports[0].to_string(), "Line 1: degree 1, f(n) = 3n");
+        assert_eq!(
+            reports[1].corrections,
+            vec![super::Correction {
+                index: 3,
+                value: 11,
+                corrected: 10,
+                degree: Some(2),
+            }]
+        );
+        assert_eq!(
+            reports[1].to_string(),
+            "Line 2: difference table never reaches an all-zero row
+  value 11 at index 3 should probably be 10"
+        );
+        assert!(reports[2].is_anomaly());
+        assert!(reports[2].corrections.is_empty());
+        assert_eq!(reports[3].to_string(), "Line 4: invalid value: x");
+    }
 }
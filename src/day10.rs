@@ -1,6 +1,4 @@
-use std::collections::HashSet;
-
-use glam::Vec2;
+use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Tile {
@@ -35,30 +33,24 @@ struct Pos {
     y: isize,
 }
 
-impl Pos {
-    fn from_index(width: isize, index: isize) -> Self {
+impl std::ops::Add for Pos {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
         Self {
-            y: index / width,
-            x: index % width,
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
         }
     }
-
-    fn to_index(self, width: isize) -> isize {
-        self.y * width + self.x
-    }
-
-    fn as_vec2(&self) -> Vec2 {
-        Vec2::new(self.x as f32, self.y as f32)
-    }
 }
 
-impl std::ops::Add for Pos {
+impl std::ops::Neg for Pos {
     type Output = Self;
 
-    fn add(self, rhs: Self) -> Self::Output {
+    fn neg(self) -> Self::Output {
         Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
+            x: -self.x,
+            y: -self.y,
         }
     }
 }
@@ -69,240 +61,479 @@ impl std::fmt::Display for Pos {
     }
 }
 
+const NORTH: Pos = Pos { x: 0, y: -1 };
+const SOUTH: Pos = Pos { x: 0, y: 1 };
+const WEST: Pos = Pos { x: -1, y: 0 };
+const EAST: Pos = Pos { x: 1, y: 0 };
+
+const PIPES: [Tile; 6] = [
+    Tile::Vertical,
+    Tile::Horizontal,
+    Tile::NorthEast,
+    Tile::NorthWest,
+    Tile::SouthWest,
+    Tile::SouthEast,
+];
+
 impl Tile {
     fn connections(&self) -> [Pos; 2] {
         match self {
-            Tile::Vertical => [Pos { x: 0, y: -1 }, Pos { x: 0, y: 1 }],
-            Tile::Horizontal => [Pos { x: -1, y: 0 }, Pos { x: 1, y: 0 }],
-            Tile::NorthEast => [Pos { x: 0, y: -1 }, Pos { x: 1, y: 0 }],
-            Tile::NorthWest => [Pos { x: 0, y: -1 }, Pos { x: -1, y: 0 }],
-            Tile::SouthWest => [Pos { x: 0, y: 1 }, Pos { x: -1, y: 0 }],
-            Tile::SouthEast => [Pos { x: 0, y: 1 }, Pos { x: 1, y: 0 }],
+            Tile::Vertical => [NORTH, SOUTH],
+            Tile::Horizontal => [WEST, EAST],
+            Tile::NorthEast => [NORTH, EAST],
+            Tile::NorthWest => [NORTH, WEST],
+            Tile::SouthWest => [SOUTH, WEST],
+            Tile::SouthEast => [SOUTH, EAST],
             _ => unreachable!(),
         }
     }
+
+    fn connects(&self, direction: Pos) -> bool {
+        PIPES.contains(self) && self.connections().contains(&direction)
+    }
+
+    fn from_connections(a: Pos, b: Pos) -> Tile {
+        PIPES
+            .into_iter()
+            .find(|pipe| pipe.connects(a) && pipe.connects(b))
+            .unwrap()
+    }
 }
 
-impl From<char> for Tile {
-    fn from(value: char) -> Self {
+impl TryFrom<char> for Tile {
+    type Error = PipeError;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
-            '|' => Self::Vertical,
-            '-' => Self::Horizontal,
-            'L' => Self::NorthEast,
-            'J' => Self::NorthWest,
-            '7' => Self::SouthWest,
-            'F' => Self::SouthEast,
-            '.' => Self::Groud,
-            'S' => Self::Starting,
-            _ => unreachable!(),
+            '|' => Ok(Self::Vertical),
+            '-' => Ok(Self::Horizontal),
+            'L' => Ok(Self::NorthEast),
+            'J' => Ok(Self::NorthWest),
+            '7' => Ok(Self::SouthWest),
+            'F' => Ok(Self::SouthEast),
+            '.' => Ok(Self::Groud),
+            'S' => Ok(Self::Starting),
+            _ => Err(PipeError::InvalidTile(value)),
         }
     }
 }
 
-fn part01(input: &str) -> u64 {
-    let map = input
-        .lines()
-        .flat_map(|line| line.chars().map(|c| c.into()))
-        .collect::<Vec<Tile>>();
-
-    let width = (map.len() as f64).sqrt() as isize;
-    let start = map.iter().position(|&t| t == Tile::Starting).unwrap();
-    let start_pos = Pos::from_index(width, start as isize);
-
-    let next = |previous: Pos, current: Pos| -> Pos {
-        let pipe = map[current.to_index(width) as usize];
-        let [mut a, mut b] = pipe.connections();
-
-        a = a + current;
-        b = b + current;
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PipeError {
+    InvalidTile(char),
+    RaggedRow(usize),
+    MissingStart,
+    MultipleStarts,
+    AmbiguousStart(Vec<Tile>),
+    BrokenLoop(Pos),
+}
 
-        if previous == a {
-            b
-        } else {
-            a
+impl std::fmt::Display for PipeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipeError::InvalidTile(c) => write!(f, "invalid tile: {c}"),
+            PipeError::RaggedRow(row) => write!(f, "row {row} has a different width"),
+            PipeError::MissingStart => write!(f, "no starting tile"),
+            PipeError::MultipleStarts => write!(f, "more than one starting tile"),
+            PipeError::AmbiguousStart(candidates) => {
+                write!(f, "starting tile could be any of ")?;
+                for candidate in candidates {
+                    write!(f, "{candidate}")?;
+                }
+                Ok(())
+            }
+            PipeError::BrokenLoop(pos) => write!(f, "loop is broken at {pos}"),
         }
-    };
-
-    let (mut a, mut b) = { calc_starting_connections(&map, width, start_pos) };
+    }
+}
 
-    let mut previous_a = start_pos;
-    let mut previous_b = start_pos;
-    let mut depth = 1;
+#[derive(Debug, Clone)]
+struct PipeMap {
+    tiles: Vec<Tile>,
+    width: isize,
+    height: isize,
+    start: Pos,
+}
 
-    loop {
-        depth += 1;
-        let next_a = next(previous_a, a);
-        let next_b = next(previous_b, b);
+impl PipeMap {
+    fn parse(input: &str) -> Result<Self, PipeError> {
+        let mut tiles = vec![];
+        let mut width = None;
+        let mut height = 0;
+
+        for (row, line) in input.lines().filter(|line| !line.is_empty()).enumerate() {
+            for c in line.chars() {
+                tiles.push(Tile::try_from(c)?);
+            }
+
+            let line_width = line.chars().count() as isize;
+            if *width.get_or_insert(line_width) != line_width {
+                return Err(PipeError::RaggedRow(row));
+            }
+            height += 1;
+        }
+        let width = width.unwrap_or(0);
 
-        if next_a == next_b {
-            break depth;
+        let mut starts = (0..tiles.len()).filter(|&index| tiles[index] == Tile::Starting);
+        let start = starts.next().ok_or(PipeError::MissingStart)? as isize;
+        if starts.next().is_some() {
+            return Err(PipeError::MultipleStarts);
         }
 
-        previous_a = a;
-        previous_b = b;
-        a = next_a;
-        b = next_b;
+        let mut map = Self {
+            tiles,
+            width,
+            height,
+            start: Pos {
+                x: start % width,
+                y: start / width,
+            },
+        };
+
+        let shape = map.start_shape()?;
+        let index = map.index(map.start).unwrap();
+        map.tiles[index] = shape;
+
+        Ok(map)
     }
-}
-
-fn calc_starting_connections(map: &[Tile], width: isize, start_pos: Pos) -> (Pos, Pos) {
-    let top = Pos { x: 0, y: -1 } + start_pos;
-    let right = Pos { x: 1, y: 0 } + start_pos;
-    let bottom = Pos { x: 0, y: 1 } + start_pos;
-    let left = Pos { x: -1, y: 0 } + start_pos;
-
-    let mut result = vec![];
-
-    if top.y >= 0 {
-        let top_tile = map[top.to_index(width) as usize];
 
-        if matches!(top_tile, Tile::Vertical | Tile::SouthEast | Tile::SouthWest) {
-            result.push(top);
-        }
+    fn index(&self, pos: Pos) -> Option<usize> {
+        ((0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y))
+            .then_some((pos.y * self.width + pos.x) as usize)
     }
 
-    if bottom.y < width {
-        let bottom_tile = map[bottom.to_index(width) as usize];
+    fn tile(&self, pos: Pos) -> Tile {
+        self.index(pos)
+            .map_or(Tile::Groud, |index| self.tiles[index])
+    }
 
-        if matches!(
-            bottom_tile,
-            Tile::Vertical | Tile::NorthWest | Tile::NorthEast
-        ) {
-            result.push(bottom);
+    // The pipe shapes `S` could be, given which of its neighbours connect back to it. With more
+    // than two such neighbours only the shapes that actually close a loop are kept.
+    fn start_shape(&self) -> Result<Tile, PipeError> {
+        let directions = [NORTH, SOUTH, WEST, EAST]
+            .into_iter()
+            .filter(|&direction| self.tile(self.start + direction).connects(-direction))
+            .collect::<Vec<_>>();
+
+        match directions.len() {
+            0 | 1 => Err(PipeError::BrokenLoop(self.start)),
+            2 => Ok(Tile::from_connections(directions[0], directions[1])),
+            _ => {
+                let candidates = PIPES
+                    .into_iter()
+                    .filter(|pipe| pipe.connections().iter().all(|d| directions.contains(d)))
+                    .filter(|&pipe| self.follow(self.start, pipe).is_ok())
+                    .collect::<Vec<_>>();
+
+                match candidates[..] {
+                    [shape] => Ok(shape),
+                    [] => Err(PipeError::BrokenLoop(self.start)),
+                    _ => Err(PipeError::AmbiguousStart(candidates)),
+                }
+            }
         }
     }
 
-    if left.x >= 0 {
-        let left_tile = map[left.to_index(width) as usize];
+    // Walks the loop through `start`, treating it as `shape`.
+    fn follow(&self, start: Pos, shape: Tile) -> Result<Vec<Pos>, PipeError> {
+        let mut loop_list = vec![start];
+        let mut direction = shape.connections()[0];
+        let mut current = start + direction;
+
+        while current != start {
+            let tile = self.tile(current);
+            if !tile.connects(-direction) {
+                return Err(PipeError::BrokenLoop(current));
+            }
+
+            loop_list.push(current);
+            direction = tile
+                .connections()
+                .into_iter()
+                .find(|&d| d != -direction)
+                .unwrap();
+            current = current + direction;
+        }
 
-        if matches!(
-            left_tile,
-            Tile::Horizontal | Tile::SouthEast | Tile::NorthEast
-        ) {
-            result.push(left);
+        if shape.connects(-direction) {
+            Ok(loop_list)
+        } else {
+            Err(PipeError::BrokenLoop(start))
         }
     }
 
-    if right.x < width {
-        let right_tile = map[right.to_index(width) as usize];
-
-        if matches!(
-            right_tile,
-            Tile::Horizontal | Tile::SouthWest | Tile::NorthWest
-        ) {
-            result.push(right);
-        }
+    fn main_loop(&self) -> Result<Vec<Pos>, PipeError> {
+        self.follow(self.start, self.tile(self.start))
     }
+}
 
-    (result[0], result[1])
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region {
+    cells: Vec<Pos>,
+    enclosed: bool,
 }
 
-fn part02(input: &str) -> u64 {
-    let map = input
-        .lines()
-        .flat_map(|line| line.chars().map(|c| c.into()))
-        .collect::<Vec<Tile>>();
+#[derive(Debug, Clone)]
+struct Analysis<'a> {
+    map: &'a PipeMap,
+    main_loop: Vec<bool>,
+    loops: Vec<Vec<Pos>>,
+    segments: Vec<Vec<Pos>>,
+    regions: Vec<Region>,
+}
 
-    let width = input.lines().next().unwrap().len() as isize;
-    let start = map.iter().position(|&t| t == Tile::Starting).unwrap();
-    let start_pos = Pos::from_index(width, start as isize);
+impl PipeMap {
+    fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| Pos { x, y }))
+    }
 
-    let next = |previous: Pos, current: Pos| -> Pos {
-        let pipe = map[current.to_index(width) as usize];
-        let [mut a, mut b] = pipe.connections();
+    // Directions in which the pipe at `pos` is joined to a neighbouring pipe.
+    fn links(&self, pos: Pos) -> Vec<Pos> {
+        let tile = self.tile(pos);
+        if !PIPES.contains(&tile) {
+            return vec![];
+        }
 
-        a = a + current;
-        b = b + current;
+        tile.connections()
+            .into_iter()
+            .filter(|&direction| self.tile(pos + direction).connects(-direction))
+            .collect()
+    }
 
-        if previous == a {
-            b
-        } else {
-            a
+    fn analysis(&self) -> Result<Analysis<'_>, PipeError> {
+        let mut main_loop = vec![false; self.tiles.len()];
+        for pos in self.main_loop()? {
+            main_loop[self.index(pos).unwrap()] = true;
         }
-    };
 
-    let (mut a, _) = { calc_starting_connections(&map, width, start_pos) };
+        let mut loops = vec![];
+        let mut segments = vec![];
+        let mut visited = vec![false; self.tiles.len()];
+
+        for pos in self.positions() {
+            if visited[self.index(pos).unwrap()] || !PIPES.contains(&self.tile(pos)) {
+                continue;
+            }
+
+            let mut component = vec![pos];
+            let mut queue = VecDeque::from([pos]);
+            visited[self.index(pos).unwrap()] = true;
+            while let Some(current) = queue.pop_front() {
+                for direction in self.links(current) {
+                    let next = current + direction;
+                    if !std::mem::replace(&mut visited[self.index(next).unwrap()], true) {
+                        component.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            match component.iter().find(|&&pos| self.links(pos).len() < 2) {
+                None => loops.push(self.follow(pos, self.tile(pos))?),
+                Some(&end) => segments.push(self.segment(end)),
+            }
+        }
 
-    let mut loop_list = vec![start_pos];
-    let mut previous_a = start_pos;
+        let regions = self.regions(&main_loop);
 
-    while a != start_pos {
-        loop_list.push(a);
-        let next_a = next(previous_a, a);
-        previous_a = a;
-        a = next_a;
+        Ok(Analysis {
+            map: self,
+            main_loop,
+            loops,
+            segments,
+            regions,
+        })
     }
 
-    let vecs = loop_list.iter().map(|p| p.as_vec2()).collect::<Vec<_>>();
-
-    let mut inside_count = 0;
-    let mut inside = HashSet::new();
+    // Walks a pipe segment that isn't closed, starting from one of its ends.
+    fn segment(&self, end: Pos) -> Vec<Pos> {
+        let mut segment = vec![end];
+        let mut previous = None;
+        let mut current = end;
+
+        while let Some(direction) = self
+            .links(current)
+            .into_iter()
+            .find(|&direction| Some(current + direction) != previous)
+        {
+            previous = Some(current);
+            current = current + direction;
+            segment.push(current);
+        }
 
-    for i in 0..map.len() {
-        let pos = Pos::from_index(width, i as isize);
+        segment
+    }
 
-        if loop_list.contains(&pos) {
-            continue;
+    // Flood fills a grid at twice the resolution, where cell (x, y) sits at (2x + 1, 2y + 1) and
+    // the points between cells are only blocked when the main loop joins them, which lets the
+    // fill squeeze between pipes that merely touch.
+    fn regions(&self, main_loop: &[bool]) -> Vec<Region> {
+        let (width, height) = (2 * self.width + 1, 2 * self.height + 1);
+        let on_loop = |pos: Pos| self.index(pos).is_some_and(|index| main_loop[index]);
+
+        let blocked = |point: Pos| {
+            let cell = Pos {
+                x: (point.x - 1).div_euclid(2),
+                y: (point.y - 1).div_euclid(2),
+            };
+            match (point.x % 2, point.y % 2) {
+                (1, 1) => on_loop(cell),
+                (0, 1) => on_loop(cell + EAST) && self.links(cell + EAST).contains(&WEST),
+                (1, 0) => on_loop(cell + SOUTH) && self.links(cell + SOUTH).contains(&NORTH),
+                _ => false,
+            }
+        };
+
+        let mut visited = vec![false; (width * height) as usize];
+        let mut regions = vec![];
+
+        for y in 0..height {
+            for x in 0..width {
+                let start = Pos { x, y };
+                if visited[(y * width + x) as usize] || blocked(start) {
+                    continue;
+                }
+
+                let mut cells = vec![];
+                let mut enclosed = true;
+                let mut queue = VecDeque::from([start]);
+                visited[(y * width + x) as usize] = true;
+
+                while let Some(point) = queue.pop_front() {
+                    if point.x == 0 || point.y == 0 || point.x == width - 1 || point.y == height - 1
+                    {
+                        enclosed = false;
+                    }
+                    if point.x % 2 == 1 && point.y % 2 == 1 {
+                        cells.push(Pos {
+                            x: point.x / 2,
+                            y: point.y / 2,
+                        });
+                    }
+
+                    for direction in [NORTH, SOUTH, WEST, EAST] {
+                        let next = point + direction;
+                        if (0..width).contains(&next.x)
+                            && (0..height).contains(&next.y)
+                            && !visited[(next.y * width + next.x) as usize]
+                            && !blocked(next)
+                        {
+                            visited[(next.y * width + next.x) as usize] = true;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+
+                if !cells.is_empty() {
+                    cells.sort_by_key(|pos| (pos.y, pos.x));
+                    regions.push(Region { cells, enclosed });
+                }
+            }
         }
 
-        let left_count = count_intersections(Pos { x: -1, y: 0 }, pos, &vecs);
-        if left_count % 2 == 0 {
-            continue;
-        }
+        regions
+    }
+}
 
-        let right_count = count_intersections(Pos { x: 1, y: 0 }, pos, &vecs);
-        if right_count % 2 == 0 {
-            continue;
-        }
+impl Analysis<'_> {
+    fn enclosed(&self) -> usize {
+        self.regions
+            .iter()
+            .filter(|region| region.enclosed)
+            .map(|region| region.cells.len())
+            .sum()
+    }
 
-        let bottom_count = count_intersections(Pos { x: 0, y: 1 }, pos, &vecs);
-        if bottom_count % 2 == 0 {
-            continue;
+    fn render(&self, colour: bool) -> String {
+        let mut enclosed = vec![false; self.map.tiles.len()];
+        for region in self.regions.iter().filter(|region| region.enclosed) {
+            for &pos in &region.cells {
+                enclosed[self.map.index(pos).unwrap()] = true;
+            }
         }
 
-        let top_count = count_intersections(Pos { x: 0, y: -1 }, pos, &vecs);
-        if top_count % 2 == 0 {
-            continue;
+        let mut output = String::new();
+        for y in 0..self.map.height {
+            for x in 0..self.map.width {
+                let pos = Pos { x, y };
+                let index = self.map.index(pos).unwrap();
+                let tile = if pos == self.map.start {
+                    Tile::Starting
+                } else {
+                    self.map.tiles[index]
+                };
+
+                if colour {
+                    let highlight = if self.main_loop[index] {
+                        GREEN
+                    } else if enclosed[index] {
+                        YELLOW
+                    } else {
+                        BLUE
+                    };
+                    output += &format!("{highlight}{tile}{RESET}");
+                } else {
+                    output += &tile.to_string();
+                }
+            }
+            output += "\n";
         }
 
-        inside_count += 1;
-        inside.insert(pos);
+        output
     }
-
-    inside_count
 }
 
-fn count_intersections(dir: Pos, position: Pos, vecs: &[Vec2]) -> usize {
-    let dir = dir.as_vec2();
-    let origin = position.as_vec2() + Vec2::new(0.0001, 0.0001);
-
-    let count = vecs
-        .windows(2)
-        .filter(|p| check_intersection(origin, dir, p[0], p[1]))
-        .count();
-
-    count
+impl std::fmt::Display for Analysis<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let enclosed = self.regions.iter().filter(|region| region.enclosed).count();
+        write!(
+            f,
+            "{} loops, {} dangling segments, {} enclosed cells in {} regions, {} outside regions",
+            self.loops.len(),
+            self.segments.len(),
+            self.enclosed(),
+            enclosed,
+            self.regions.len() - enclosed
+        )
+    }
 }
 
-fn check_intersection(origin: Vec2, dir: Vec2, point_a: Vec2, point_b: Vec2) -> bool {
-    let v1 = origin - point_a;
-    let v2 = point_b - point_a;
-    let v3 = Vec2::new(-dir.y, dir.x);
-
-    let dot = v2.dot(v3);
-
-    if dot.abs() < 0.00001 {
-        return false;
-    }
+fn part01(input: &str) -> u64 {
+    let map = PipeMap::parse(input).unwrap();
+    map.main_loop().unwrap().len() as u64 / 2
+}
 
-    let t1 = v2.perp_dot(v1) / dot;
-    let t2 = v1.dot(v3) / dot;
+fn part02(input: &str) -> u64 {
+    let map = PipeMap::parse(input).unwrap();
+    interior_count(&map.main_loop().unwrap())
+}
 
-    t1 >= 0.0 && (0.0..=1.0).contains(&t2)
+// Twice the area enclosed by the loop from the shoelace formula, then Pick's theorem
+// (A = i + b / 2 - 1) gives the number of cells strictly inside it.
+fn interior_count(loop_list: &[Pos]) -> u64 {
+    let double_area = loop_list
+        .iter()
+        .zip(loop_list.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<isize>()
+        .unsigned_abs() as u64;
+
+    (double_area + 2 - loop_list.len() as u64) / 2
 }
 
 fn main() {
     let input = include_str!("../input/day10.input");
+    if std::env::args().any(|arg| arg == "--render") {
+        let map = PipeMap::parse(input).unwrap();
+        let analysis = map.analysis().unwrap();
+        print!("{}", analysis.render(true));
+        println!("{analysis}");
+    }
     println!("Part 01: {}", part01(input));
     println!("Part 02: {}", part02(input));
 }
@@ -312,31 +543,94 @@ mod test {
     use super::*;
 
     #[test]
-    fn check_intersection_intersects() {
-        let origin = Vec2::new(0.0, 5.0);
-        let dir = Vec2::new(1.0, 0.0);
-        let point_a = Vec2::new(0.0, 0.0);
-        let point_b = Vec2::new(10.0, 10.0);
-        assert!(check_intersection(origin, dir, point_a, point_b));
+    fn interior_count() {
+        let square = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+        ]
+        .map(|(x, y)| Pos { x, y });
+        assert_eq!(super::interior_count(&square), 1);
+
+        let mut reversed = square;
+        reversed.reverse();
+        assert_eq!(super::interior_count(&reversed), 1);
+
+        let tight = [(0, 0), (1, 0), (1, 1), (0, 1)].map(|(x, y)| Pos { x, y });
+        assert_eq!(super::interior_count(&tight), 0);
     }
 
     #[test]
-    fn check_intersection_perpendicular() {
-        let origin = Vec2::new(0.0, 0.0);
-        let dir = Vec2::new(0.0, 1.0);
-        let point_a = Vec2::new(0.0, 2.0);
-        let point_b = Vec2::new(0.0, 5.0);
-        assert!(!check_intersection(origin, dir, point_a, point_b));
+    fn rectangular() {
+        let input = ".....
+.S-7.
+.|.|.
+.L-J.";
+        assert_eq!(super::part01(input), 4);
+        assert_eq!(super::part02(input), 1);
+
+        let input = "S-7
+L-J";
+        assert_eq!(super::part01(input), 3);
+        assert_eq!(super::part02(input), 0);
     }
 
     #[test]
-    fn test_check_intersection() {
-        let origin = Vec2::new(1.0, 1.0);
-        let dir = Vec2::new(-1.0, 0.0);
-        let point_a = Vec2::new(0.0, 0.0);
-        let point_b = Vec2::new(0.0, 2.0);
+    fn start_shape() {
+        let input = "F-7..
+|.|..
+L-S-7
+..|.|
+..L-J";
+        assert_eq!(
+            PipeMap::parse(input).unwrap_err(),
+            PipeError::AmbiguousStart(vec![Tile::NorthWest, Tile::SouthEast])
+        );
+
+        let input = "F-7..
+|.|..
+L-S-7
+..|.|
+..L-.";
+        let map = PipeMap::parse(input).unwrap();
+        assert_eq!(map.tile(map.start), Tile::NorthWest);
+        assert_eq!(map.main_loop().unwrap().len(), 8);
+
+        let map = PipeMap::parse("..F7.\n.FJ|.\nSJ.L7\n|F--J\nLJ...").unwrap();
+        assert_eq!(map.tile(map.start), Tile::SouthEast);
+    }
 
-        assert!(super::check_intersection(origin, dir, point_a, point_b));
+    #[test]
+    fn pipe_errors() {
+        assert_eq!(
+            PipeMap::parse("F7\nLJ").unwrap_err(),
+            PipeError::MissingStart
+        );
+        assert_eq!(
+            PipeMap::parse("S7\nSJ").unwrap_err(),
+            PipeError::MultipleStarts
+        );
+        assert_eq!(
+            PipeMap::parse("S-7\nL-x").unwrap_err(),
+            PipeError::InvalidTile('x')
+        );
+        assert_eq!(
+            PipeMap::parse("S-7\nL-J-").unwrap_err(),
+            PipeError::RaggedRow(1)
+        );
+        assert_eq!(
+            PipeMap::parse("S-7\n..|").unwrap_err(),
+            PipeError::BrokenLoop(Pos { x: 0, y: 0 })
+        );
+        assert_eq!(
+            PipeMap::parse("S-7\n|..\nL-J").unwrap().main_loop(),
+            Err(PipeError::BrokenLoop(Pos { x: 2, y: 1 }))
+        );
     }
 
     #[test]
@@ -404,5 +698,92 @@ L.L7LFJ|||||FJL7||LJ
 L7JLJL-JLJLJL--JLJ.L";
         assert_eq!(super::part02(input), 10);
     }
-}
 
+    // The old ray casts skipped the segment closing the loop back into `S`, so the ray from
+    // the cell right of `S` saw no crossing and the cell was counted as outside.
+    #[test]
+    fn part02_closing_segment() {
+        let input = ".....
+.F-7.
+.S.|.
+.L-J.
+.....";
+        assert_eq!(super::part02(input), 1);
+    }
+
+    #[test]
+    fn analysis() {
+        let input = "S7F7
+LJLJ
+F-..";
+        let map = PipeMap::parse(input).unwrap();
+        let analysis = map.analysis().unwrap();
+
+        assert_eq!(analysis.loops.len(), 2);
+        assert_eq!(
+            analysis.segments,
+            vec![vec![Pos { x: 0, y: 2 }, Pos { x: 1, y: 2 }]]
+        );
+        assert_eq!(analysis.regions.len(), 1);
+        assert!(!analysis.regions[0].enclosed);
+        assert_eq!(analysis.regions[0].cells.len(), 8);
+        assert_eq!(analysis.render(false), "S┐┌┐\n└┘└┘\n┌─..\n");
+        assert!(analysis
+            .render(true)
+            .starts_with("\x1b[32mS\x1b[0m\x1b[32m┐"));
+        assert!(analysis.to_string().ends_with(
+            "2 loops, 1 dangling segments, 0 enclosed cells in 0 regions, 1 outside regions"
+        ));
+    }
+
+    #[test]
+    fn analysis_squeeze() {
+        // The middle cells are only reachable from the outside by squeezing between the two
+        // vertical pipes at the bottom, while the two enclosed pockets are joined by squeezing
+        // between the parallel pipes of the loop itself.
+        let input = "..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+..........";
+        let map = PipeMap::parse(input).unwrap();
+        let analysis = map.analysis().unwrap();
+
+        assert_eq!(analysis.enclosed(), 4);
+        assert_eq!(analysis.regions.iter().filter(|r| r.enclosed).count(), 1);
+        assert_eq!(analysis.regions.iter().filter(|r| !r.enclosed).count(), 1);
+
+        for input in [
+            ".F----7F7F7F7F-7....
+.|F--7||||||||FJ....
+.||.FJ||||||||L7....
+FJL7L7LJLJ||LJ.L-7..
+L--J.L7...LJS7F-7L7.
+....F-J..F7FJ|L7L7L7
+....L7.F7||L7|.L7L7|
+.....|FJLJ|FJ|F7|.LJ
+....FJL-7.||.||||...
+....L---J.LJ.LJLJ...",
+            "FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L",
+        ] {
+            let map = PipeMap::parse(input).unwrap();
+            assert_eq!(
+                map.analysis().unwrap().enclosed() as u64,
+                super::interior_count(&map.main_loop().unwrap())
+            );
+        }
+    }
+}
@@ -1,162 +1,378 @@
-use std::collections::HashSet;
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum MapCell {
-    Galaxy,
-    Void(i32, i32),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Row,
+    Column,
 }
 
-impl MapCell {
-    fn distance(&self) -> (i32, i32) {
-        match *self {
-            MapCell::Galaxy => (1, 1),
-            MapCell::Void(w, h) => (w, h),
-        }
-    }
+// A single row or column of the image, as seen by an expansion rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Line {
+    axis: Axis,
+    length: usize,
+    galaxies: usize,
+    // Number of consecutive empty lines this one belongs to, 0 when it holds a galaxy.
+    empty_run: usize,
 }
 
-fn expand_galaxy(input: &str, distance: i32) -> Vec<Vec<MapCell>> {
-    let mut map = input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| {
-                    if c == '#' {
-                        MapCell::Galaxy
-                    } else {
-                        MapCell::Void(1, 1)
-                    }
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
+#[derive(Debug, Clone, Copy)]
+enum Expansion {
+    // Every empty row and column becomes `factor` lines wide.
+    Uniform(u64),
+    #[allow(dead_code)]
+    Separate {
+        rows: u64,
+        columns: u64,
+    },
+    // Every line in a run of `n` empty lines becomes `factor * n` wide.
+    #[allow(dead_code)]
+    Consecutive(u64),
+    // Lines grow with their share of empty cells, a fully empty line becomes `factor` wide.
+    #[allow(dead_code)]
+    Density(u64),
+    #[allow(dead_code)]
+    Custom(fn(&Line) -> u64),
+}
 
-    for row in map.iter_mut() {
-        if row.iter_mut().all(|c| matches!(c, MapCell::Void(..))) {
-            row.iter_mut().for_each(|c| {
-                if let MapCell::Void(_, ref mut h) = c {
-                    *h = distance;
+impl Expansion {
+    fn width(&self, line: &Line) -> u64 {
+        let empty = line.galaxies == 0;
+        match *self {
+            Expansion::Uniform(factor) => {
+                if empty {
+                    factor
+                } else {
+                    1
+                }
+            }
+            Expansion::Separate { rows, columns } => match (empty, line.axis) {
+                (false, _) => 1,
+                (true, Axis::Row) => rows,
+                (true, Axis::Column) => columns,
+            },
+            Expansion::Consecutive(factor) => {
+                if empty {
+                    factor * line.empty_run as u64
+                } else {
+                    1
                 }
-            });
+            }
+            Expansion::Density(factor) => {
+                let empty_cells = (line.length - line.galaxies) as u64;
+                1 + factor.saturating_sub(1) * empty_cells / line.length.max(1) as u64
+            }
+            Expansion::Custom(width) => width(line),
         }
     }
 
-    let width = map.first().unwrap().len();
-    for i in 0..width {
-        if map
-            .iter()
-            .all(|row| matches!(row.get(i).unwrap(), MapCell::Void(..)))
-        {
-            map.iter_mut().for_each(|row| {
-                if let MapCell::Void(ref mut w, _) = row.get_mut(i).unwrap() {
-                    *w = distance;
-                }
-            })
+    // The same rule with another factor, only for rules where every line width is linear in
+    // the factor. Density rounds and Separate has two factors, so neither qualifies.
+    #[cfg(test)]
+    fn with_factor(&self, factor: u64) -> Option<Expansion> {
+        match self {
+            Expansion::Uniform(_) => Some(Expansion::Uniform(factor)),
+            Expansion::Consecutive(_) => Some(Expansion::Consecutive(factor)),
+            _ => None,
         }
     }
-
-    map
 }
 
-fn calc_distance(source: (i32, i32), dest: (i32, i32), distance: (i32, i32)) -> i32 {
-    let x_offset = (dest.0 - source.0).abs() * distance.0;
-    let y_offset = (dest.1 - source.1).abs() * distance.1;
-    x_offset + y_offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ExpandedMap {
+    galaxies: Vec<(i32, i32)>,
+    // Expanded position of every column and row, so the distance between two galaxies is the
+    // difference of their offsets.
+    columns: Vec<u64>,
+    rows: Vec<u64>,
 }
 
-fn calc_path_distance(map: &[Vec<MapCell>], path: &[(i32, i32)]) -> u64 {
-    path.windows(2)
-        .map(|pair| {
-            let a = pair[0];
-            let b = pair[1];
+fn expand_lines(counts: &[usize], axis: Axis, length: usize, expansion: &Expansion) -> Vec<u64> {
+    let mut runs = vec![0; counts.len()];
+    let mut start = 0;
+    for end in 0..=counts.len() {
+        if end == counts.len() || counts[end] != 0 {
+            runs[start..end].fill(end - start);
+            start = end + 1;
+        }
+    }
 
-            let distance_b_a = map[b.1 as usize][b.0 as usize].distance();
-            calc_distance(a, b, distance_b_a) as u64
+    let mut offset = 0;
+    counts
+        .iter()
+        .zip(runs)
+        .map(|(&galaxies, empty_run)| {
+            let line = Line {
+                axis,
+                length,
+                galaxies,
+                empty_run,
+            };
+            let start = offset;
+            offset += expansion.width(&line);
+            start
         })
-        .sum()
+        .collect()
 }
 
-fn parse_galaxy_map(input: &str, galaxy_distance: i32) -> u64 {
-    let map = expand_galaxy(input, galaxy_distance);
+fn expand_galaxy(input: &str, expansion: &Expansion) -> ExpandedMap {
+    let image = input
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let (width, height) = (image[0].len(), image.len());
 
-    let galaxies = map
+    let galaxies = image
         .iter()
         .enumerate()
-        .flat_map(|(y, chars)| {
-            chars.iter().enumerate().filter_map(move |(x, c)| {
-                if *c == MapCell::Galaxy {
-                    Some((x as i32, y as i32))
-                } else {
-                    None
-                }
-            })
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, &galaxy)| galaxy)
+                .map(move |(x, _)| (x as i32, y as i32))
         })
         .collect::<Vec<_>>();
 
-    galaxies
-        .iter()
-        .enumerate()
-        .flat_map(|(idx, source)| {
-            galaxies
-                .iter()
-                .skip(idx + 1)
-                .map(|&dest| find_shortest_path(&map, &mut HashSet::new(), *source, dest))
+    let mut column_counts = vec![0; width];
+    let mut row_counts = vec![0; height];
+    for &(x, y) in &galaxies {
+        column_counts[x as usize] += 1;
+        row_counts[y as usize] += 1;
+    }
+
+    ExpandedMap {
+        columns: expand_lines(&column_counts, Axis::Column, height, expansion),
+        rows: expand_lines(&row_counts, Axis::Row, width, expansion),
+        galaxies,
+    }
+}
+
+fn pair_distance(map: &ExpandedMap, source: (i32, i32), dest: (i32, i32)) -> u64 {
+    map.columns[source.0 as usize].abs_diff(map.columns[dest.0 as usize])
+        + map.rows[source.1 as usize].abs_diff(map.rows[dest.1 as usize])
+}
+
+// `constant + slope * factor`, for expansion rules where every line width is linear in the
+// factor, which then holds for every distance as well.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Linear {
+    constant: i128,
+    slope: i128,
+}
+
+#[cfg(test)]
+impl Linear {
+    fn from_evaluations(
+        expansion: &Expansion,
+        evaluate: impl Fn(Expansion) -> u128,
+    ) -> Option<Self> {
+        let one = evaluate(expansion.with_factor(1)?) as i128;
+        let two = evaluate(expansion.with_factor(2)?) as i128;
+        Some(Self {
+            constant: 2 * one - two,
+            slope: two - one,
         })
-        .map(|path| calc_path_distance(&map, &path))
-        .sum()
+    }
+
+    fn evaluate(&self, factor: u64) -> i128 {
+        self.constant + self.slope * factor as i128
+    }
 }
 
-fn part01(input: &str) -> u64 {
-    parse_galaxy_map(input, 2)
+// Once sorted, every value is larger than all of the values before it, so it contributes
+// `index * value - sum of previous values` to the total.
+fn pairwise_distance_sum(mut values: Vec<u64>) -> u128 {
+    values.sort_unstable();
+
+    let mut previous = 0u128;
+    let mut total = 0u128;
+    for (index, &value) in values.iter().enumerate() {
+        total += index as u128 * value as u128 - previous;
+        previous += value as u128;
+    }
+
+    total
 }
 
-fn part02(input: &str) -> u64 {
-    parse_galaxy_map(input, 1_000_000)
+fn total_distance(map: &ExpandedMap) -> u128 {
+    pairwise_distance_sum(
+        map.galaxies
+            .iter()
+            .map(|g| map.columns[g.0 as usize])
+            .collect(),
+    ) + pairwise_distance_sum(
+        map.galaxies
+            .iter()
+            .map(|g| map.rows[g.1 as usize])
+            .collect(),
+    )
 }
 
-fn find_shortest_path(
-    map: &[Vec<MapCell>],
-    visited: &mut HashSet<(i32, i32)>,
-    source: (i32, i32),
-    dest: (i32, i32),
-) -> Vec<(i32, i32)> {
-    const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+#[derive(Debug, Clone)]
+struct Universe {
+    map: ExpandedMap,
+}
 
-    if source == dest {
-        return vec![source];
+impl Universe {
+    fn new(input: &str, expansion: Expansion) -> Self {
+        Self {
+            map: expand_galaxy(input, &expansion),
+        }
     }
 
-    visited.insert(source);
+    fn total_distance(&self) -> u128 {
+        total_distance(&self.map)
+    }
 
-    let mut nexts = DIRECTIONS
-        .iter()
-        .map(|dir| (source.0 + dir.0, source.1 + dir.1))
-        .filter(|source| {
-            source.0 >= 0
-                && (source.0 as usize) < map[0].len()
-                && source.1 >= 0
-                && (source.1 as usize) < map.len()
-                && !visited.contains(source)
-        })
-        .collect::<Vec<_>>();
+    fn galaxies(&self) -> &[(i32, i32)] {
+        &self.map.galaxies
+    }
+
+    fn distance(&self, a: usize, b: usize) -> u64 {
+        pair_distance(&self.map, self.map.galaxies[a], self.map.galaxies[b])
+    }
+
+    fn others(&self, galaxy: usize) -> impl Iterator<Item = (usize, u64)> + '_ {
+        (0..self.map.galaxies.len())
+            .filter(move |&other| other != galaxy)
+            .map(move |other| (other, self.distance(galaxy, other)))
+    }
+
+    fn nearest(&self, galaxy: usize) -> Option<(usize, u64)> {
+        self.others(galaxy).min_by_key(|&(_, distance)| distance)
+    }
+
+    fn farthest(&self, galaxy: usize) -> Option<(usize, u64)> {
+        self.others(galaxy)
+            .max_by_key(|&(other, distance)| (distance, std::cmp::Reverse(other)))
+    }
 
-    nexts.sort_unstable_by(|&a, &b| {
-        calc_distance(a, dest, (1, 1)).cmp(&calc_distance(b, dest, (1, 1)))
-    });
+    // The largest Manhattan distance is the largest spread of either x + y or x - y, so the
+    // farthest pair only needs the extremes of both.
+    fn farthest_pair(&self) -> Option<(usize, usize, u64)> {
+        let position = |galaxy: usize| {
+            let (x, y) = self.map.galaxies[galaxy];
+            (
+                self.map.columns[x as usize] as i128,
+                self.map.rows[y as usize] as i128,
+            )
+        };
+
+        let galaxies = 0..self.map.galaxies.len();
+        [|(x, y): (i128, i128)| x + y, |(x, y): (i128, i128)| x - y]
+            .into_iter()
+            .filter_map(|key| {
+                let min = galaxies.clone().min_by_key(|&g| key(position(g)))?;
+                let max = galaxies.clone().max_by_key(|&g| key(position(g)))?;
+                (min != max).then(|| (min.min(max), min.max(max), self.distance(min, max)))
+            })
+            .max_by_key(|&(_, _, distance)| distance)
+    }
+
+    fn histogram(&self) -> std::collections::BTreeMap<u64, usize> {
+        let mut histogram = std::collections::BTreeMap::new();
+        for a in 0..self.map.galaxies.len() {
+            for b in a + 1..self.map.galaxies.len() {
+                *histogram.entry(self.distance(a, b)).or_default() += 1;
+            }
+        }
+        histogram
+    }
+
+    // Cells of the unexpanded image along one shortest path, horizontal first.
+    fn path(&self, a: usize, b: usize) -> Vec<(i32, i32)> {
+        let ((x1, y1), (x2, y2)) = (self.map.galaxies[a], self.map.galaxies[b]);
+        let step = |from: i32, to: i32| if to >= from { 1 } else { -1 };
+
+        let mut path = vec![(x1, y1)];
+        let (mut x, mut y) = (x1, y1);
+        while x != x2 {
+            x += step(x1, x2);
+            path.push((x, y));
+        }
+        while y != y2 {
+            y += step(y1, y2);
+            path.push((x, y));
+        }
+
+        path
+    }
+
+    fn render_path(&self, a: usize, b: usize) -> String {
+        let path = self.path(a, b);
+        let mut output = String::new();
+
+        for y in 0..self.map.rows.len() as i32 {
+            for x in 0..self.map.columns.len() as i32 {
+                output.push(if self.map.galaxies.contains(&(x, y)) {
+                    '#'
+                } else if path.contains(&(x, y)) {
+                    'o'
+                } else {
+                    '.'
+                });
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
 
-    for next in nexts {
-        let mut next_path = find_shortest_path(map, visited, next, dest);
-        if !next_path.is_empty() {
-            next_path.insert(0, source);
-            return next_path;
+impl std::fmt::Display for Universe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let galaxies = 0..self.galaxies().len();
+        write!(f, "{} galaxies", galaxies.len())?;
+
+        if let Some((a, b, distance)) = self.farthest_pair() {
+            write!(f, ", farthest pair {a}-{b} at {distance}")?;
+        }
+        if let Some((a, b, distance)) = galaxies
+            .clone()
+            .filter_map(|a| self.nearest(a).map(|(b, distance)| (a, b, distance)))
+            .min_by_key(|&(_, _, distance)| distance)
+        {
+            write!(f, ", closest pair {a}-{b} at {distance}")?;
+        }
+        if let Some((galaxy, distance)) = galaxies
+            .filter_map(|galaxy| {
+                self.farthest(galaxy)
+                    .map(|(_, distance)| (galaxy, distance))
+            })
+            .min_by_key(|&(_, distance)| distance)
+        {
+            write!(f, ", most central galaxy {galaxy} within {distance}")?;
         }
+        if let Some((distance, count)) =
+            self.histogram().into_iter().max_by_key(|&(_, count)| count)
+        {
+            write!(f, ", most common distance {distance} ({count} pairs)")?;
+        }
+
+        Ok(())
     }
+}
+
+fn parse_galaxy_map(input: &str, galaxy_distance: u64) -> u128 {
+    Universe::new(input, Expansion::Uniform(galaxy_distance)).total_distance()
+}
+
+fn part01(input: &str) -> u128 {
+    parse_galaxy_map(input, 2)
+}
 
-    vec![]
+fn part02(input: &str) -> u128 {
+    parse_galaxy_map(input, 1_000_000)
 }
 
 fn main() {
     let input = include_str!("../input/day11.input");
+    if std::env::args().any(|arg| arg == "--render") {
+        let universe = Universe::new(input, Expansion::Uniform(2));
+        if let Some((a, b, _)) = universe.farthest_pair() {
+            print!("{}", universe.render_path(a, b));
+        }
+        println!("{universe}");
+    }
     println!("Part 01: {}", part01(input));
     println!("Part 02: {}", part02(input));
 }
@@ -195,60 +411,54 @@ mod test {
 
     #[test]
     fn part01_path_5_9() {
-        let grid = expand_galaxy(INPUT, 2);
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(2));
 
-        let path = find_shortest_path(&grid, &mut HashSet::new(), GALAXIES[4], GALAXIES[8]);
-        let distance = calc_path_distance(&grid, &path);
+        let distance = pair_distance(&grid, GALAXIES[4], GALAXIES[8]);
 
         assert_eq!(distance, 9);
     }
 
     #[test]
     fn part01_path_1_7() {
-        let grid = expand_galaxy(INPUT, 2);
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(2));
 
-        let path = find_shortest_path(&grid, &mut HashSet::new(), GALAXIES[0], GALAXIES[6]);
-        let distance = calc_path_distance(&grid, &path);
+        let distance = pair_distance(&grid, GALAXIES[0], GALAXIES[6]);
 
         assert_eq!(distance, 15);
     }
 
     #[test]
     fn part01_path_3_6() {
-        let grid = expand_galaxy(INPUT, 2);
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(2));
 
-        let path = find_shortest_path(&grid, &mut HashSet::new(), GALAXIES[2], GALAXIES[5]);
-        let distance = calc_path_distance(&grid, &path);
+        let distance = pair_distance(&grid, GALAXIES[2], GALAXIES[5]);
 
         assert_eq!(distance, 17);
     }
 
     #[test]
     fn part01_path_8_9() {
-        let grid = expand_galaxy(INPUT, 2);
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(2));
 
-        let path = find_shortest_path(&grid, &mut HashSet::new(), GALAXIES[7], GALAXIES[8]);
-        let distance = calc_path_distance(&grid, &path);
+        let distance = pair_distance(&grid, GALAXIES[7], GALAXIES[8]);
 
         assert_eq!(distance, 5);
     }
 
     #[test]
     fn part01_path_7_9() {
-        let grid = expand_galaxy(INPUT, 2);
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(2));
 
-        let path = find_shortest_path(&grid, &mut HashSet::new(), GALAXIES[6], GALAXIES[8]);
-        let distance = calc_path_distance(&grid, &path);
+        let distance = pair_distance(&grid, GALAXIES[6], GALAXIES[8]);
 
         assert_eq!(distance, 5);
     }
 
     #[test]
     fn part01_path_1_4() {
-        let grid = expand_galaxy(INPUT, 2);
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(2));
 
-        let path = find_shortest_path(&grid, &mut HashSet::new(), GALAXIES[0], GALAXIES[3]);
-        let distance = calc_path_distance(&grid, &path);
+        let distance = pair_distance(&grid, GALAXIES[0], GALAXIES[3]);
 
         assert_eq!(distance, 9);
     }
@@ -262,5 +472,163 @@ mod test {
     fn part02_100() {
         assert_eq!(super::parse_galaxy_map(INPUT, 100), 8410);
     }
-}
 
+    #[test]
+    fn pairwise_distance_sum() {
+        assert_eq!(super::pairwise_distance_sum(vec![]), 0);
+        assert_eq!(super::pairwise_distance_sum(vec![5]), 0);
+        assert_eq!(super::pairwise_distance_sum(vec![7, 1, 4]), 12);
+        assert_eq!(
+            super::pairwise_distance_sum(vec![u64::MAX, 0, u64::MAX]),
+            2 * u64::MAX as u128
+        );
+    }
+
+    #[test]
+    fn pairwise_matches_direct() {
+        let grid = expand_galaxy(INPUT, &Expansion::Uniform(1_000));
+        let direct = GALAXIES
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &a)| GALAXIES[i + 1..].iter().map(move |&b| (a, b)))
+            .map(|(a, b)| pair_distance(&grid, a, b) as u128)
+            .sum::<u128>();
+
+        assert_eq!(super::parse_galaxy_map(INPUT, 1_000), direct);
+    }
+
+    #[test]
+    fn expansion_rules() {
+        let total = |expansion| total_distance(&expand_galaxy(INPUT, &expansion));
+
+        assert_eq!(
+            total(Expansion::Separate {
+                rows: 2,
+                columns: 2
+            }),
+            374
+        );
+        assert_eq!(
+            total(Expansion::Separate {
+                rows: 10,
+                columns: 10
+            }),
+            total(Expansion::Uniform(10))
+        );
+        assert_eq!(
+            total(Expansion::Separate {
+                rows: 1,
+                columns: 1
+            }),
+            292
+        );
+        // The example never has two empty lines next to each other.
+        assert_eq!(total(Expansion::Consecutive(10)), 1030);
+        assert_eq!(total(Expansion::Density(1)), 292);
+        assert_eq!(total(Expansion::Custom(|_| 1)), 292);
+
+        let map = expand_galaxy("#..#\n....\n....\n#...", &Expansion::Consecutive(3));
+        assert_eq!(map.columns, vec![0, 1, 7, 13]);
+        assert_eq!(map.rows, vec![0, 1, 7, 13]);
+
+        // A row with one galaxy out of four cells is three quarters empty.
+        let map = expand_galaxy("#...\n....\n#..#", &Expansion::Density(5));
+        assert_eq!(map.rows, vec![0, 4, 9]);
+        assert_eq!(map.columns, vec![0, 2, 7, 12]);
+
+        assert_eq!(total(Expansion::Density(0)), 292);
+    }
+
+    #[test]
+    fn linear_in_factor() {
+        let total = |expansion| total_distance(&expand_galaxy(INPUT, &expansion));
+
+        let linear = Linear::from_evaluations(&Expansion::Uniform(2), total).unwrap();
+        assert_eq!(linear.evaluate(10), 1030);
+        assert_eq!(linear.evaluate(100), 8410);
+
+        let pair = Linear::from_evaluations(&Expansion::Consecutive(2), |expansion| {
+            let map = expand_galaxy(INPUT, &expansion);
+            pair_distance(&map, GALAXIES[0], GALAXIES[6]) as u128
+        });
+        assert_eq!(
+            pair,
+            Some(Linear {
+                constant: 9,
+                slope: 3
+            })
+        );
+
+        for expansion in [
+            Expansion::Density(2),
+            Expansion::Separate {
+                rows: 2,
+                columns: 3,
+            },
+            Expansion::Custom(|_| 1),
+        ] {
+            assert!(expansion.with_factor(10).is_none());
+            assert_eq!(Linear::from_evaluations(&expansion, total), None);
+        }
+    }
+
+    #[test]
+    fn universe() {
+        let universe = Universe::new(INPUT, Expansion::Uniform(2));
+
+        assert_eq!(universe.galaxies(), GALAXIES);
+        assert_eq!(universe.distance(4, 8), 9);
+        assert_eq!(universe.nearest(7), Some((8, 5)));
+        assert_eq!(universe.nearest(0), Some((1, 6)));
+        assert_eq!(universe.farthest(0), Some((5, 15)));
+        assert_eq!(universe.farthest_pair(), Some((1, 7, 19)));
+
+        let histogram = universe.histogram();
+        assert_eq!(histogram.values().sum::<usize>(), 36);
+        assert_eq!(
+            histogram
+                .iter()
+                .map(|(&d, &n)| d as u128 * n as u128)
+                .sum::<u128>(),
+            universe.total_distance()
+        );
+        assert_eq!(histogram.keys().max(), Some(&19));
+
+        assert_eq!(
+            universe.to_string(),
+            "9 galaxies, farthest pair 1-7 at 19, closest pair 1-3 at 5, \
+             most central galaxy 4 within 13, most common distance 9 (7 pairs)"
+        );
+
+        let universe = Universe::new(INPUT, Expansion::Uniform(10));
+        assert_eq!(universe.total_distance(), 1030);
+        assert_eq!(
+            universe.farthest_pair().map(|(_, _, distance)| distance),
+            universe.histogram().keys().max().copied()
+        );
+    }
+
+    #[test]
+    fn path() {
+        let universe = Universe::new(INPUT, Expansion::Uniform(2));
+        let path = universe.path(4, 8);
+
+        assert_eq!(path.first(), Some(&GALAXIES[4]));
+        assert_eq!(path.last(), Some(&GALAXIES[8]));
+        assert_eq!(path.len(), 8);
+        assert_eq!(
+            universe.render_path(4, 8),
+            "...#......
+.......#..
+#.........
+..........
+......#...
+.#ooo.....
+....o....#
+....o.....
+....o..#..
+#...#.....
+"
+        );
+    }
+}
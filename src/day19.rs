@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use advent_of_code::interval::IntervalSet;
+
 #[derive(Debug, Clone, Copy)]
 enum Action<'a> {
     Accept,
@@ -7,7 +9,7 @@ enum Action<'a> {
     Goto(&'a str),
 }
 impl Action<'_> {
-    fn parse(step: &str) -> Action {
+    fn parse(step: &str) -> Action<'_> {
         if step == "R" {
             Action::Reject
         } else if step == "A" {
@@ -45,46 +47,24 @@ impl Prop {
         }
     }
 
-    fn set_min(&self, part: PartRange, value: u32) -> PartRange {
+    fn get_range<'a>(&self, part: &'a PartRange) -> &'a IntervalSet {
         match self {
-            Prop::X => PartRange {
-                x: (value, part.x.1),
-                ..part
-            },
-            Prop::M => PartRange {
-                m: (value, part.m.1),
-                ..part
-            },
-            Prop::A => PartRange {
-                a: (value, part.a.1),
-                ..part
-            },
-            Prop::S => PartRange {
-                s: (value, part.s.1),
-                ..part
-            },
+            Prop::X => &part.x,
+            Prop::M => &part.m,
+            Prop::A => &part.a,
+            Prop::S => &part.s,
         }
     }
 
-    fn set_max(&self, part: PartRange, value: u32) -> PartRange {
+    fn set_range(&self, part: &PartRange, values: IntervalSet) -> PartRange {
+        let mut part = part.clone();
         match self {
-            Prop::X => PartRange {
-                x: (part.x.0, value),
-                ..part
-            },
-            Prop::M => PartRange {
-                m: (part.m.0, value),
-                ..part
-            },
-            Prop::A => PartRange {
-                a: (part.a.0, value),
-                ..part
-            },
-            Prop::S => PartRange {
-                s: (part.s.0, value),
-                ..part
-            },
+            Prop::X => part.x = values,
+            Prop::M => part.m = values,
+            Prop::A => part.a = values,
+            Prop::S => part.s = values,
         }
+        part
     }
 }
 
@@ -114,10 +94,18 @@ impl Op {
         }
     }
 
-    fn slice(&self, part: PartRange) -> (PartRange, PartRange) {
+    fn slice(&self, part: &PartRange) -> (PartRange, PartRange) {
+        let (prop, below) = match self {
+            Op::Lt(prop, value) => (prop, IntervalSet::from(0..*value as u64)),
+            Op::Gt(prop, value) => (prop, IntervalSet::from(0..*value as u64 + 1)),
+        };
+
+        let values = prop.get_range(part);
+        let (below, above) = (values.intersection(&below), values.difference(&below));
+
         match self {
-            Op::Lt(prop, value) => (prop.set_max(part, value - 1), prop.set_min(part, *value)),
-            Op::Gt(prop, value) => (prop.set_min(part, value + 1), prop.set_max(part, *value)),
+            Op::Lt(..) => (prop.set_range(part, below), prop.set_range(part, above)),
+            Op::Gt(..) => (prop.set_range(part, above), prop.set_range(part, below)),
         }
     }
 }
@@ -141,29 +129,26 @@ impl Part {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 struct PartRange {
-    x: (u32, u32),
-    m: (u32, u32),
-    a: (u32, u32),
-    s: (u32, u32),
+    x: IntervalSet,
+    m: IntervalSet,
+    a: IntervalSet,
+    s: IntervalSet,
 }
 
 impl PartRange {
     fn full() -> Self {
         Self {
-            x: (1, 4000),
-            m: (1, 4000),
-            a: (1, 4000),
-            s: (1, 4000),
+            x: (1..4001).into(),
+            m: (1..4001).into(),
+            a: (1..4001).into(),
+            s: (1..4001).into(),
         }
     }
 
     fn combinations(&self) -> u64 {
-        ((self.x.1 - self.x.0) as u64 + 1)
-            * ((self.m.1 - self.m.0) as u64 + 1)
-            * ((self.a.1 - self.a.0) as u64 + 1)
-            * ((self.s.1 - self.s.0) as u64 + 1)
+        self.x.len() * self.m.len() * self.a.len() * self.s.len()
     }
 }
 
@@ -208,11 +193,10 @@ fn count_combinations(
             .steps
             .iter()
             .fold((0, range), |(combinations, range), (maybe_op, action)| {
-                let (left, maybe_right) = if let Some(op) = maybe_op {
-                    let (left, right) = op.slice(range);
-                    (left, Some(right))
+                let (left, right) = if let Some(op) = maybe_op {
+                    op.slice(&range)
                 } else {
-                    (range, None)
+                    (range, PartRange::default())
                 };
 
                 let left_combinations = match action {
@@ -221,9 +205,7 @@ fn count_combinations(
                     Action::Goto(next) => count_combinations(next, left, workflows),
                 };
 
-                let range = maybe_right.unwrap_or(range);
-
-                (combinations + left_combinations, range)
+                (combinations + left_combinations, right)
             });
 
     combinations
@@ -344,4 +326,3 @@ hdj{m>838:A,pv}
         assert_eq!(super::part02(INPUT), 167409079868000);
     }
 }
-
@@ -0,0 +1,194 @@
+use std::ops::Range;
+
+// Sorted set of disjoint, non-adjacent half-open intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet(Vec<Range<u64>>);
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let first = self.0.partition_point(|r| r.end < range.start);
+        let last = self.0.partition_point(|r| r.start <= range.end);
+
+        let start = self.0[first..last]
+            .first()
+            .map_or(range.start, |r| r.start.min(range.start));
+        let end = self.0[first..last]
+            .last()
+            .map_or(range.end, |r| r.end.max(range.end));
+
+        self.0.splice(first..last, std::iter::once(start..end));
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut union = self.clone();
+        for range in other.iter() {
+            union.insert(range.clone());
+        }
+        union
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intersection = vec![];
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.0.len() && j < other.0.len() {
+            let (a, b) = (&self.0[i], &other.0[j]);
+
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                intersection.push(start..end);
+            }
+
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet(intersection)
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut difference = vec![];
+        let mut j = 0;
+
+        for range in &self.0 {
+            let mut start = range.start;
+
+            while j < other.0.len() && other.0[j].end <= start {
+                j += 1;
+            }
+
+            let mut k = j;
+            while k < other.0.len() && other.0[k].start < range.end {
+                if other.0[k].start > start {
+                    difference.push(start..other.0[k].start);
+                }
+                start = start.max(other.0[k].end);
+                k += 1;
+            }
+
+            if start < range.end {
+                difference.push(start..range.end);
+            }
+        }
+
+        IntervalSet(difference)
+    }
+
+    // Moves every value so that `from` lands on `to`. Values pushed past `u64::MAX` are dropped,
+    // as the half-open ranges can't hold them.
+    pub fn shift(&self, from: u64, to: u64) -> IntervalSet {
+        let shift = |value: u64| {
+            if to >= from {
+                value.saturating_add(to - from)
+            } else {
+                value - (from - to)
+            }
+        };
+        self.0
+            .iter()
+            .map(|range| shift(range.start)..shift(range.end))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Range<u64>> {
+        self.0.iter()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = u64> + '_ {
+        self.0.iter().flat_map(|range| range.clone())
+    }
+
+    pub fn len(&self) -> u64 {
+        self.0.iter().map(|range| range.end - range.start).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        let index = self.0.partition_point(|range| range.end <= value);
+        self.0.get(index).is_some_and(|range| range.start <= value)
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        self.0.first().map(|range| range.start)
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        self.0.last().map(|range| range.end - 1)
+    }
+}
+
+impl From<Range<u64>> for IntervalSet {
+    fn from(value: Range<u64>) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(value);
+        set
+    }
+}
+
+impl FromIterator<Range<u64>> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Range<u64>>>(iter: T) -> Self {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalSet;
+
+    #[test]
+    fn insert() {
+        let set = IntervalSet::from_iter([10..20, 30..40, 5..5, 20..25, 0..3, 2..11]);
+
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), vec![0..25, 30..40]);
+        assert_eq!(set.len(), 35);
+        assert_eq!(set.min(), Some(0));
+        assert_eq!(set.max(), Some(39));
+        assert!(set.contains(24));
+        assert!(!set.contains(25));
+        assert!(set.contains(30));
+    }
+
+    #[test]
+    fn operations() {
+        let a = IntervalSet::from_iter([0..10, 20..30]);
+        let b = IntervalSet::from_iter([5..25, 40..50]);
+
+        assert_eq!(a.union(&b), IntervalSet::from_iter([0..30, 40..50]));
+        assert_eq!(a.intersection(&b), IntervalSet::from_iter([5..10, 20..25]));
+        assert_eq!(a.difference(&b), IntervalSet::from_iter([0..5, 25..30]));
+        assert_eq!(b.difference(&a), IntervalSet::from_iter([10..20, 40..50]));
+        assert_eq!(a.difference(&a), IntervalSet::new());
+        assert_eq!(a.shift(5, 20), IntervalSet::from_iter([15..25, 35..45]));
+        assert_eq!(b.shift(5, 0), IntervalSet::from_iter([0..20, 35..45]));
+        assert_eq!(
+            a.shift(0, u64::MAX - 5),
+            IntervalSet::from(u64::MAX - 5..u64::MAX)
+        );
+    }
+
+    #[test]
+    fn points() {
+        let set = IntervalSet::from_iter([1..3, 7..9]);
+
+        assert_eq!(set.points().collect::<Vec<_>>(), vec![1, 2, 7, 8]);
+    }
+}
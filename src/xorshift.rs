@@ -0,0 +1,40 @@
+// Small deterministic generator for randomised tests.
+#[derive(Debug, Clone)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "seed must not be zero");
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.next_u64() as usize % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorShift;
+
+    #[test]
+    fn deterministic() {
+        let mut a = XorShift::new(42);
+        let mut b = XorShift::new(42);
+
+        for _ in 0..100 {
+            let value = a.next_u64();
+            assert_ne!(value, 0);
+            assert_eq!(value, b.next_u64());
+            assert!(a.below(7) < 7);
+            b.below(7);
+        }
+    }
+}